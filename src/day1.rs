@@ -1,46 +1,192 @@
+use std::str::FromStr;
+
 fn main() {
+    let policy = std::env::args()
+        .nth(1)
+        .map(|arg| {
+            arg.parse()
+                .expect("invalid policy, use skip, zero or error")
+        })
+        .unwrap_or(MissingNumberPolicy::Skip);
+
+    let scripts = std::env::args()
+        .nth(2)
+        .map(|arg| {
+            arg.split(',')
+                .map(|script| script.parse().expect("invalid digit script"))
+                .collect::<Vec<DigitScript>>()
+        })
+        .unwrap_or_else(|| vec![DigitScript::Ascii]);
+
     let input = include_str!("assets/day1/input");
-    let result = solve(input);
 
-    println!("Result is {}", result);
+    match solve(input, policy, &scripts) {
+        Ok(calibration) => {
+            if !calibration.skipped_lines.is_empty() {
+                println!(
+                    "Skipped {} lines without numbers: {:?}",
+                    calibration.skipped_lines.len(),
+                    calibration.skipped_lines
+                );
+            }
+            println!("Result is {}", calibration.sum);
+        }
+        Err(err) => eprintln!("{}", err),
+    }
+}
+
+// what to do with lines that contain neither a digit nor a number word
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MissingNumberPolicy {
+    Skip,
+    Zero,
+    Error,
+}
+
+impl FromStr for MissingNumberPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(MissingNumberPolicy::Skip),
+            "zero" => Ok(MissingNumberPolicy::Zero),
+            "error" => Ok(MissingNumberPolicy::Error),
+            _ => Err(format!("unknown policy: {}", s)),
+        }
+    }
+}
+
+// decimal digit scripts accepted as numeric chars, each is a contiguous block of ten code points
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DigitScript {
+    Ascii,
+    ArabicIndic,
+    ExtendedArabicIndic,
+    Devanagari,
+    Bengali,
+    Thai,
+    Fullwidth,
+}
+
+impl DigitScript {
+    const ALL: [DigitScript; 7] = [
+        DigitScript::Ascii,
+        DigitScript::ArabicIndic,
+        DigitScript::ExtendedArabicIndic,
+        DigitScript::Devanagari,
+        DigitScript::Bengali,
+        DigitScript::Thai,
+        DigitScript::Fullwidth,
+    ];
+
+    fn zero(&self) -> char {
+        match self {
+            DigitScript::Ascii => '0',
+            DigitScript::ArabicIndic => '\u{0660}',
+            DigitScript::ExtendedArabicIndic => '\u{06F0}',
+            DigitScript::Devanagari => '\u{0966}',
+            DigitScript::Bengali => '\u{09E6}',
+            DigitScript::Thai => '\u{0E50}',
+            DigitScript::Fullwidth => '\u{FF10}',
+        }
+    }
+
+    fn digit_value(&self, c: char) -> Option<u8> {
+        let offset = (c as u32).checked_sub(self.zero() as u32)?;
+
+        (offset < 10).then_some(offset as u8)
+    }
+}
+
+impl FromStr for DigitScript {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(DigitScript::Ascii),
+            "arabic-indic" => Ok(DigitScript::ArabicIndic),
+            "extended-arabic-indic" => Ok(DigitScript::ExtendedArabicIndic),
+            "devanagari" => Ok(DigitScript::Devanagari),
+            "bengali" => Ok(DigitScript::Bengali),
+            "thai" => Ok(DigitScript::Thai),
+            "fullwidth" => Ok(DigitScript::Fullwidth),
+            _ => Err(format!("unknown digit script: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct MissingNumberError {
+    line: usize,
+}
+
+impl std::fmt::Display for MissingNumberError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no number found in line {}", self.line)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Calibration {
+    sum: u64,
+    // 1-based line numbers of lines without any number
+    skipped_lines: Vec<usize>,
 }
 
-fn solve(input: &str) -> u64 {
-    let input = parse_input(input);
-    input.iter().map(|num| *num as u64).sum()
+fn solve(
+    input: &str,
+    policy: MissingNumberPolicy,
+    scripts: &[DigitScript],
+) -> Result<Calibration, MissingNumberError> {
+    let input = parse_input(input, scripts);
+
+    input
+        .iter()
+        .enumerate()
+        .try_fold(Calibration::default(), |mut calibration, (index, num)| {
+            match (num, policy) {
+                (Some(num), _) => calibration.sum += *num as u64,
+                (None, MissingNumberPolicy::Skip) => calibration.skipped_lines.push(index + 1),
+                (None, MissingNumberPolicy::Zero) => (),
+                (None, MissingNumberPolicy::Error) => {
+                    return Err(MissingNumberError { line: index + 1 })
+                }
+            }
+
+            Ok(calibration)
+        })
 }
 
-fn parse_input(text: &str) -> Vec<u8> {
+fn parse_input(text: &str, scripts: &[DigitScript]) -> Vec<Option<u8>> {
     text.lines()
-        .map(parse_number)
+        .map(|line| parse_number(line, scripts))
         .collect()
 }
 
-fn parse_number(line: &str) -> u8 {
+fn parse_number(line: &str, scripts: &[DigitScript]) -> Option<u8> {
     let mut numeric_words = line.numeric_words();
     numeric_words.sort_by_key(|(_, index)| *index);
 
+    // byte offsets to match the ones returned by match_indices
     let mut numeric_chars: Vec<NumericChar> = line
-        .chars()
-        .enumerate()
-        .filter(|(_index, char)| char.is_numeric())
+        .char_indices()
+        .filter(|(_index, char)| {
+            scripts
+                .iter()
+                .any(|script| script.digit_value(*char).is_some())
+        })
         .map(|(index, char)| (char, index))
         .collect();
     numeric_chars.sort_by_key(|(_, index)| *index);
 
-    let first = parse_first_number(&numeric_words, &numeric_chars);
-    let second = parse_last_number(&numeric_words, &numeric_chars);
+    let first = parse_first_number(&numeric_words, &numeric_chars)?;
+    let second = parse_last_number(&numeric_words, &numeric_chars)?;
 
-    format!("{first}{second}")
-        .parse()
-        .expect("chars not numeric")
+    Some(first * 10 + second)
 }
 
-fn parse_first_number(numeric_words: &[NumericWord], numeric_chars: &[NumericChar]) -> u8 {
-    match (
-        numeric_chars.first().is_some(),
-        numeric_words.first().is_some(),
-    ) {
+fn parse_first_number(numeric_words: &[NumericWord], numeric_chars: &[NumericChar]) -> Option<u8> {
+    let number = match (!numeric_chars.is_empty(), !numeric_words.is_empty()) {
         (true, false) => numeric_chars
             .first()
             .expect("no numbers present")
@@ -62,15 +208,14 @@ fn parse_first_number(numeric_words: &[NumericWord], numeric_chars: &[NumericCha
                     .to_number()
             }
         }
-        (false, false) => unreachable!("no numbers should never be present"),
-    }
+        (false, false) => return None,
+    };
+
+    Some(number)
 }
 
-fn parse_last_number(numeric_words: &[NumericWord], numeric_chars: &[NumericChar]) -> u8 {
-    match (
-        numeric_chars.last().is_some(),
-        numeric_words.last().is_some(),
-    ) {
+fn parse_last_number(numeric_words: &[NumericWord], numeric_chars: &[NumericChar]) -> Option<u8> {
+    let number = match (!numeric_chars.is_empty(), !numeric_words.is_empty()) {
         (true, false) => numeric_chars
             .last()
             .expect("no numbers present")
@@ -92,8 +237,10 @@ fn parse_last_number(numeric_words: &[NumericWord], numeric_chars: &[NumericChar
                     .to_number()
             }
         }
-        (false, false) => unreachable!("no numbers should never be present"),
-    }
+        (false, false) => return None,
+    };
+
+    Some(number)
 }
 
 type NumericWord = (String, usize);
@@ -105,11 +252,10 @@ trait IntoNumber {
 
 impl IntoNumber for NumericChar {
     fn to_number(&self) -> u8 {
-        if !self.0.is_numeric() {
-            panic!("invalid char")
-        } else {
-            self.0.to_string().parse().expect("invalid numeric number")
-        }
+        DigitScript::ALL
+            .iter()
+            .find_map(|script| script.digit_value(self.0))
+            .expect("invalid numeric char")
     }
 }
 
@@ -144,9 +290,78 @@ impl NumericWords for &str {
             .iter()
             .flat_map(|word| {
                 self.match_indices(word)
-                .map(|(index, _)| (word.to_string(), index))
-                .collect::<Vec<NumericWord>>()
+                    .map(|(index, _)| (word.to_string(), index))
+                    .collect::<Vec<NumericWord>>()
             })
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "two1nine\neightwothree\nabcone2threexyz\nxtwone3four\n4nineeightseven2\nzoneight234\n7pqrstsixteen";
+
+    #[test]
+    fn it_works() {
+        let result = solve(INPUT, MissingNumberPolicy::Error, &[DigitScript::Ascii]).unwrap();
+        assert_eq!(result.sum, 281);
+        assert!(result.skipped_lines.is_empty());
+    }
+
+    #[test]
+    fn it_skips_lines_without_numbers() {
+        let input = "header\ntwo1nine\n\neightwothree";
+        let result = solve(input, MissingNumberPolicy::Skip, &[DigitScript::Ascii]).unwrap();
+        assert_eq!(result.sum, 29 + 83);
+        assert_eq!(result.skipped_lines, vec![1, 3]);
+    }
+
+    #[test]
+    fn it_treats_lines_without_numbers_as_zero() {
+        let input = "header\ntwo1nine\n\neightwothree";
+        let result = solve(input, MissingNumberPolicy::Zero, &[DigitScript::Ascii]).unwrap();
+        assert_eq!(result.sum, 29 + 83);
+        assert!(result.skipped_lines.is_empty());
+    }
+
+    #[test]
+    fn it_errors_on_lines_without_numbers() {
+        let input = "two1nine\n\neightwothree";
+        let result = solve(input, MissingNumberPolicy::Error, &[DigitScript::Ascii]);
+        assert_eq!(result, Err(MissingNumberError { line: 2 }));
+    }
+
+    #[test]
+    fn it_ignores_non_decimal_numerics() {
+        let input = "½two3¾";
+        let result = solve(input, MissingNumberPolicy::Error, &DigitScript::ALL).unwrap();
+        assert_eq!(result.sum, 23);
+    }
+
+    #[test]
+    fn it_converts_non_ascii_digits_of_accepted_scripts() {
+        let input = "\u{0663}abc7\nx\u{FF15}y";
+
+        let result = solve(input, MissingNumberPolicy::Skip, &[DigitScript::Ascii]).unwrap();
+        assert_eq!(result.sum, 77);
+        assert_eq!(result.skipped_lines, vec![2]);
+
+        let scripts = [
+            DigitScript::Ascii,
+            DigitScript::ArabicIndic,
+            DigitScript::Fullwidth,
+        ];
+        let result = solve(input, MissingNumberPolicy::Error, &scripts).unwrap();
+        assert_eq!(result.sum, 37 + 55);
+    }
+
+    #[test]
+    fn it_compares_byte_offsets_of_words_and_chars() {
+        // multibyte chars shift the byte offset of "two" past the char index of '1'
+        let input = "éééétwo1";
+        let result = solve(input, MissingNumberPolicy::Error, &[DigitScript::Ascii]).unwrap();
+        assert_eq!(result.sum, 21);
+    }
+}
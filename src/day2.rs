@@ -1,134 +1,800 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::str::FromStr;
+
+// colour -> number of cubes
+type Bag = BTreeMap<String, u64>;
+
+const COLOURS: [&str; 3] = ["red", "green", "blue"];
+
+// how a colour that never shows up in a game counts towards its power
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MissingColour {
+    // no cubes of that colour are needed, the power becomes 0
+    Zero,
+    // the colour is ignored, it does not change the power
+    One,
+}
+
+#[derive(Debug, PartialEq)]
 struct Game {
     id: u64,
     sets: Vec<GameSet>,
 }
 
 impl Game {
-    fn min_red(&self) -> u64 {
-        self.sets.iter().map(|set| set.red).max().unwrap().unwrap_or(1)
+    // fewest cubes of each colour shown in any set of the game
+    fn min_bag(&self) -> Bag {
+        self.sets.iter().fold(Bag::new(), |mut bag, set| {
+            for (colour, count) in set.cubes.iter() {
+                let min = bag.entry(colour.clone()).or_insert(0);
+                *min = (*min).max(*count);
+            }
+            bag
+        })
+    }
+
+    // None if the colour is never shown in any set
+    fn min_cubes(&self, colour: &str) -> Option<u64> {
+        self.sets
+            .iter()
+            .filter_map(|set| set.cubes.get(colour).copied())
+            .max()
+    }
+
+    fn game_power(&self, colours: &[&str], missing: MissingColour) -> u64 {
+        colours
+            .iter()
+            .map(|colour| match (self.min_cubes(colour), missing) {
+                (Some(count), _) => count,
+                (None, MissingColour::Zero) => 0,
+                (None, MissingColour::One) => 1,
+            })
+            .product()
+    }
+
+    fn is_possible(&self, bag: &Bag) -> bool {
+        self.sets.iter().all(|set| set.is_valid(bag))
+    }
+
+    // each set is drawn without replacement, cubes go back into the bag between sets
+    fn likelihood(&self, bag: &Bag) -> f64 {
+        self.sets.iter().map(|set| set.probability(bag)).product()
     }
 
-    fn min_blue(&self) -> u64 {
-        self.sets.iter().map(|set| set.blue).max().unwrap().unwrap_or(1)
+    // bag with `total` cubes that explains the observed sets best, colours of the game
+    // are always part of the bag, ties are broken by the order of the compositions
+    fn max_likelihood_bag(&self, colours: &[&str], total: u64) -> Option<(Bag, f64)> {
+        let mut min_bag = self.min_bag();
+        for colour in colours {
+            min_bag.entry(colour.to_string()).or_insert(0);
+        }
+
+        bags_with_total(&min_bag, total)
+            .into_iter()
+            .map(|bag| {
+                let likelihood = self.likelihood(&bag);
+                (bag, likelihood)
+            })
+            .fold(
+                None,
+                |best: Option<(Bag, f64)>, (bag, likelihood)| match best {
+                    Some((_, best_likelihood)) if best_likelihood >= likelihood => best,
+                    _ => Some((bag, likelihood)),
+                },
+            )
+    }
+
+    // probability that drawing sets of the same sizes from `bag` yields a game
+    // that is possible under `limits`
+    fn possible_probability(&self, bag: &Bag, limits: &Bag) -> f64 {
+        self.sets
+            .iter()
+            .map(|set| {
+                let size = set.cubes.values().sum::<u64>();
+                draw_within_limits_probability(bag, limits, size)
+            })
+            .product()
+    }
+}
+
+fn binomial(n: u64, k: u64) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+// all bags with exactly `total` cubes holding at least the cubes of `min_bag`
+fn bags_with_total(min_bag: &Bag, total: u64) -> Vec<Bag> {
+    fn distribute(colours: &[&String], remaining: u64, bag: &mut Bag, bags: &mut Vec<Bag>) {
+        match colours.split_first() {
+            None if remaining == 0 => bags.push(bag.clone()),
+            None => (),
+            Some((colour, rest)) => {
+                let min = bag[*colour];
+                for extra in 0..=remaining {
+                    bag.insert(colour.to_string(), min + extra);
+                    distribute(rest, remaining - extra, bag, bags);
+                }
+                bag.insert(colour.to_string(), min);
+            }
+        }
     }
 
-    fn min_green(&self) -> u64 {
-        self.sets.iter().map(|set| set.green).max().unwrap().unwrap_or(1)
+    let min_total = min_bag.values().sum::<u64>();
+    let mut bags = Vec::new();
+
+    if min_total <= total {
+        let colours: Vec<_> = min_bag.keys().collect();
+        distribute(&colours, total - min_total, &mut min_bag.clone(), &mut bags);
     }
 
-    fn game_power(&self) -> u64 {
-        self.min_red() * self.min_blue() * self.min_green()
+    bags
+}
+
+fn draw_within_limits_probability(bag: &Bag, limits: &Bag, size: u64) -> f64 {
+    let total = bag.values().sum::<u64>();
+    if size > total {
+        return 0.0;
     }
+
+    // ways[drawn] = number of ways to draw `drawn` cubes within limits from the colours so far
+    let ways = bag.iter().fold(vec![1.0], |ways, (colour, count)| {
+        let cap = (*count).min(limits.get(colour).copied().unwrap_or(0));
+        let mut next = vec![0.0; (ways.len() as u64 + cap) as usize];
+
+        for (drawn, ways) in ways.iter().enumerate() {
+            for picked in 0..=cap {
+                next[drawn + picked as usize] += ways * binomial(*count, picked);
+            }
+        }
+
+        next
+    });
+
+    ways.get(size as usize).copied().unwrap_or(0.0) / binomial(total, size)
 }
 
+#[derive(Debug, PartialEq)]
 struct GameSet {
-    red: Option<u64>,
-    blue: Option<u64>,
-    green: Option<u64>,
+    cubes: Bag,
 }
 
 impl GameSet {
+    fn new(cubes: Bag) -> GameSet {
+        GameSet { cubes }
+    }
+
+    // colours missing from the bag have a limit of zero
+    fn is_valid(&self, bag: &Bag) -> bool {
+        self.cubes
+            .iter()
+            .all(|(colour, count)| *count <= bag.get(colour).copied().unwrap_or(0))
+    }
 
-    fn new(red: Option<u64>, blue: Option<u64>, green: Option<u64>) -> GameSet {
-        GameSet {
-            red,
-            blue,
-            green,
+    // multivariate hypergeometric probability of drawing exactly this set from the bag
+    fn probability(&self, bag: &Bag) -> f64 {
+        if !self.is_valid(bag) {
+            return 0.0;
         }
+
+        let total = bag.values().sum::<u64>();
+        let size = self.cubes.values().sum::<u64>();
+
+        let ways = bag
+            .iter()
+            .map(|(colour, count)| binomial(*count, self.cubes.get(colour).copied().unwrap_or(0)))
+            .product::<f64>();
+
+        ways / binomial(total, size)
     }
+}
 
-    fn is_valid(&self, max_red: u64, max_green: u64, max_blue: u64) -> bool {
-        let valid_red = self.red.map(|red| red <= max_red).unwrap_or(true);
-        let valid_green = self.green.map(|green| green <= max_green).unwrap_or(true);
-        let valid_blue = self.blue.map(|blue| blue <= max_blue).unwrap_or(true);
+impl std::fmt::Display for GameSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cubes: Vec<_> = self
+            .cubes
+            .iter()
+            .map(|(colour, count)| format!("{} {}", count, colour))
+            .collect();
 
-        valid_red && valid_green && valid_blue
+        write!(f, "{}", cubes.join(", "))
     }
 }
 
-impl From<&str> for GameSet {
-    fn from(s: &str) -> GameSet {
-        let colors: Vec<(String, u64)> = s.split(',').map(|s| {
-            let parts: Vec<_> = s.split(' ').filter(|part| !part.is_empty()).collect();
-            let color = parts.last().expect("invalid format").to_string();
-            let value: u64 = parts
-                .first()
-                .expect("invalid format")
-                .parse::<u64>()
-                .expect("Invalid format");
-            (color, value)
-        }).collect();
-        let red = colors.iter().find(|(color, _)| color == "red").map(|(_, value)| *value);
-        let blue = colors.iter().find(|(color, _)| color == "blue").map(|(_, value)| *value);
-        let green = colors.iter().find(|(color, _)| color == "green").map(|(_, value)| *value);
-
-        GameSet::new(red, blue, green)
-    }
-}
-
-impl From<&str> for Game {
-    fn from(s: &str) -> Game {
-        let id = s
-            .split(':')
-            .next()
-            .expect("invalid format")
-            .split(' ')
-            .nth(1)
-            .unwrap()
-            .parse::<u64>()
-            .unwrap();
+impl std::fmt::Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Game {}:", self.id)?;
 
-        let raw_sets = s.split(':').nth(1).expect("invalid format");
-        let sets: Vec<_> = raw_sets.split(';').map(GameSet::from).collect();
+        if !self.sets.is_empty() {
+            let sets: Vec<_> = self.sets.iter().map(|set| set.to_string()).collect();
+            write!(f, " {}", sets.join("; "))?;
+        }
 
-        Game { id, sets }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ParseErrorKind {
+    UnexpectedChar(char),
+    Expected(&'static str),
+    InvalidNumber,
+    DuplicateColour(String),
+}
+
+// span is a byte range within the line, line is 1-based
+#[derive(Debug, PartialEq)]
+struct ParseError {
+    kind: ParseErrorKind,
+    line: usize,
+    span: Range<usize>,
+}
+
+impl ParseError {
+    fn new(kind: ParseErrorKind, span: Range<usize>) -> ParseError {
+        ParseError {
+            kind,
+            line: 1,
+            span,
+        }
     }
 }
 
-fn solve_part1(input: &str) -> u64 {
-    let games: Vec<_> = input.lines().map(Game::from).collect();
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, bytes {:?}: ", self.line, self.span)?;
+
+        match &self.kind {
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            ParseErrorKind::Expected(expected) => write!(f, "expected {}", expected),
+            ParseErrorKind::InvalidNumber => write!(f, "number out of range"),
+            ParseErrorKind::DuplicateColour(colour) => write!(f, "duplicate colour {}", colour),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Word,
+    Number,
+    Colon,
+    Semicolon,
+    Comma,
+}
+
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    span: Range<usize>,
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token<'_>>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            ':' => TokenKind::Colon,
+            ';' => TokenKind::Semicolon,
+            ',' => TokenKind::Comma,
+            c if c.is_ascii_digit() => {
+                while chars.next_if(|(_, c)| c.is_ascii_digit()).is_some() {}
+                TokenKind::Number
+            }
+            c if c.is_alphabetic() => {
+                while chars.next_if(|(_, c)| c.is_alphabetic()).is_some() {}
+                TokenKind::Word
+            }
+            c => {
+                return Err(ParseError::new(
+                    ParseErrorKind::UnexpectedChar(c),
+                    start..start + c.len_utf8(),
+                ))
+            }
+        };
+
+        let end = chars.peek().map(|(index, _)| *index).unwrap_or(line.len());
+        tokens.push(Token {
+            kind,
+            text: &line[start..end],
+            span: start..end,
+        });
+    }
+
+    Ok(tokens)
+}
+
+// recursive descent over the grammar
+// game := "Game" number ":" [set (";" set)*]
+// set  := number colour ("," number colour)*
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    position: usize,
+    len: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(line: &'a str) -> Result<Parser<'a>, ParseError> {
+        Ok(Parser {
+            tokens: tokenize(line)?,
+            position: 0,
+            len: line.len(),
+        })
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.position)
+    }
+
+    fn expect(
+        &mut self,
+        kind: TokenKind,
+        expected: &'static str,
+    ) -> Result<&Token<'a>, ParseError> {
+        match self.tokens.get(self.position) {
+            Some(token) if token.kind == kind => {
+                self.position += 1;
+                Ok(token)
+            }
+            Some(token) => Err(ParseError::new(
+                ParseErrorKind::Expected(expected),
+                token.span.clone(),
+            )),
+            None => Err(ParseError::new(
+                ParseErrorKind::Expected(expected),
+                self.len..self.len,
+            )),
+        }
+    }
+
+    fn number(&mut self) -> Result<u64, ParseError> {
+        let token = self.expect(TokenKind::Number, "number")?;
+
+        token
+            .text
+            .parse()
+            .map_err(|_| ParseError::new(ParseErrorKind::InvalidNumber, token.span.clone()))
+    }
+
+    fn game(&mut self) -> Result<Game, ParseError> {
+        let keyword = self.expect(TokenKind::Word, "'Game'")?;
+        if !keyword.text.eq_ignore_ascii_case("game") {
+            return Err(ParseError::new(
+                ParseErrorKind::Expected("'Game'"),
+                keyword.span.clone(),
+            ));
+        }
+
+        let id = self.number()?;
+        self.expect(TokenKind::Colon, "':'")?;
+
+        let mut sets = Vec::new();
+
+        if self.peek().is_some() {
+            sets.push(self.set()?);
+
+            while self.peek().is_some() {
+                self.expect(TokenKind::Semicolon, "',' or ';'")?;
+                sets.push(self.set()?);
+            }
+        }
+
+        Ok(Game { id, sets })
+    }
+
+    fn set(&mut self) -> Result<GameSet, ParseError> {
+        let mut cubes = Bag::new();
+
+        loop {
+            let count = self.number()?;
+            let colour = self.expect(TokenKind::Word, "colour")?;
+
+            if cubes.insert(colour.text.to_lowercase(), count).is_some() {
+                return Err(ParseError::new(
+                    ParseErrorKind::DuplicateColour(colour.text.to_lowercase()),
+                    colour.span.clone(),
+                ));
+            }
+
+            match self.peek() {
+                Some(token) if token.kind == TokenKind::Comma => self.position += 1,
+                _ => break,
+            }
+        }
+
+        Ok(GameSet::new(cubes))
+    }
+}
+
+impl FromStr for Game {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s)?.game()
+    }
+}
+
+// blank lines are skipped, errors carry the line they occurred in
+fn parse_games(input: &str) -> Result<Vec<Game>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            line.parse::<Game>().map_err(|err| ParseError {
+                line: index + 1,
+                ..err
+            })
+        })
+        .collect()
+}
+
+fn default_bag() -> Bag {
+    Bag::from([
+        ("red".to_string(), 12),
+        ("green".to_string(), 13),
+        ("blue".to_string(), 14),
+    ])
+}
+
+// parses bags in the form "red=12,green=13,blue=14"
+fn parse_bag(s: &str) -> Result<Bag, String> {
+    s.split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(|part| {
+            let (colour, count) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected colour=count, got {}", part))?;
+            let count = count
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("invalid count for {}: {}", colour, count))?;
+
+            Ok((colour.trim().to_string(), count))
+        })
+        .collect()
+}
+
+struct GamePossibility {
+    id: u64,
+    // every bag containing at least these cubes makes the game possible
+    min_bag: Bag,
+    // indices of the candidate bags the game is possible with
+    possible_bags: Vec<usize>,
+}
+
+fn possible_bags(games: &[Game], bags: &[Bag]) -> Vec<GamePossibility> {
     games
         .iter()
-        .filter(|game| {
-            game.sets.iter().all(|set| set.is_valid(12, 13, 14))
+        .map(|game| GamePossibility {
+            id: game.id,
+            min_bag: game.min_bag(),
+            possible_bags: bags
+                .iter()
+                .enumerate()
+                .filter(|(_, bag)| game.is_possible(bag))
+                .map(|(index, _)| index)
+                .collect(),
         })
+        .collect()
+}
+
+fn solve_part1(input: &str, bag: &Bag) -> u64 {
+    let games = parse_games(input).expect("invalid game records");
+    games
+        .iter()
+        .filter(|game| game.is_possible(bag))
         .map(|game| game.id)
         .sum::<u64>()
 }
 
-fn solve_part2(input: &str) -> u64 {
-    let games: Vec<_> = input.lines().map(Game::from).collect();
+fn solve_part2(input: &str, colours: &[&str], missing: MissingColour) -> u64 {
+    let games = parse_games(input).expect("invalid game records");
 
-    games.iter().map(|game| game.game_power()).sum::<u64>()
+    games
+        .iter()
+        .map(|game| game.game_power(colours, missing))
+        .sum::<u64>()
 }
 
 fn main() {
+    // every --bag flag adds a candidate bag, part 1 uses the first one
+    let args: Vec<_> = std::env::args().skip(1).collect();
+    let mut bags: Vec<Bag> = args
+        .windows(2)
+        .filter(|pair| pair[0] == "--bag")
+        .map(|pair| parse_bag(&pair[1]).expect("invalid bag"))
+        .collect();
+
+    if bags.is_empty() {
+        bags.push(default_bag());
+    }
+
     let input = include_str!("assets/day2/input");
-    let result = solve_part1(input);
+    let result = solve_part1(input, &bags[0]);
     println!("Sum of valid ids: {}", result);
 
-    let result = solve_part2(input);
+    if bags.len() > 1 {
+        let games = parse_games(input).expect("invalid game records");
+        for possibility in possible_bags(&games, &bags) {
+            println!(
+                "Game {} (min bag {:?}) is possible with bags {:?}",
+                possibility.id, possibility.min_bag, possibility.possible_bags
+            );
+        }
+    }
+
+    // guess the bag with the given number of cubes for every game
+    let guess_total = args
+        .windows(2)
+        .find(|pair| pair[0] == "--guess-total")
+        .map(|pair| pair[1].parse::<u64>().expect("invalid total"));
+
+    if let Some(total) = guess_total {
+        let games = parse_games(input).expect("invalid game records");
+        for game in games.iter() {
+            match game.max_likelihood_bag(&COLOURS, total) {
+                Some((bag, likelihood)) => println!(
+                    "Game {}: most likely bag {:?} (likelihood {:e}), possible with bag {:?} in {:.2}% of games",
+                    game.id,
+                    bag,
+                    likelihood,
+                    bags[0],
+                    game.possible_probability(&bag, &bags[0]) * 100.0
+                ),
+                None => println!("Game {}: needs more than {} cubes", game.id, total),
+            }
+        }
+    }
+
+    let missing = match args
+        .windows(2)
+        .find(|pair| pair[0] == "--missing-colour")
+        .map(|pair| pair[1].as_str())
+    {
+        Some("one") => MissingColour::One,
+        Some("zero") | None => MissingColour::Zero,
+        Some(other) => panic!("invalid missing colour convention: {}", other),
+    };
+
+    let result = solve_part2(input, &COLOURS, missing);
     println!("Sum of game powers: {}", result);
 }
 
-
 #[cfg(test)]
 mod tests {
-    use crate::{solve_part1, solve_part2};
-
+    use crate::{
+        default_bag, parse_bag, parse_games, possible_bags, solve_part1, solve_part2, Bag, Game,
+        MissingColour, ParseError, ParseErrorKind, COLOURS,
+    };
 
     #[test]
     fn it_works() {
         let input = include_str!("assets/day2/input_test");
-        let result = solve_part1(input);
+        let result = solve_part1(input, &default_bag());
         assert_eq!(result, 8);
     }
 
+    #[test]
+    fn it_works_with_custom_bag() {
+        let input = include_str!("assets/day2/input_test");
+        let bag = parse_bag("red=20, green=13, blue=14").unwrap();
+        let result = solve_part1(input, &bag);
+        assert_eq!(result, 1 + 2 + 3 + 5);
+
+        assert!(parse_bag("red:12").is_err());
+        assert!(parse_bag("red=many").is_err());
+    }
+
+    #[test]
+    fn it_lists_possible_bags_per_game() {
+        let input = include_str!("assets/day2/input_test");
+        let games = parse_games(input).expect("invalid game records");
+        let bags = [default_bag(), parse_bag("red=20,green=13,blue=14").unwrap()];
+
+        let possibilities = possible_bags(&games, &bags);
+
+        assert_eq!(possibilities.len(), 5);
+        assert_eq!(possibilities[0].id, 1);
+        assert_eq!(possibilities[0].possible_bags, vec![0, 1]);
+        assert_eq!(possibilities[2].possible_bags, vec![1]);
+        assert_eq!(possibilities[3].possible_bags, Vec::<usize>::new());
+        assert_eq!(
+            possibilities[2].min_bag,
+            Bag::from([
+                ("red".to_string(), 20),
+                ("green".to_string(), 13),
+                ("blue".to_string(), 6),
+            ])
+        );
+    }
+
     #[test]
     fn it_works_part2() {
         let input = include_str!("assets/day2/input_test");
-        let result = solve_part2(input);
+        let result = solve_part2(input, &COLOURS, MissingColour::Zero);
+        assert_eq!(result, 2286);
+
+        let result = solve_part2(input, &COLOURS, MissingColour::One);
         assert_eq!(result, 2286);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn it_distinguishes_missing_colours() {
+        let game = "Game 1: 3 blue, 4 red; 1 red, 6 blue"
+            .parse::<Game>()
+            .unwrap();
+
+        assert_eq!(game.min_cubes("red"), Some(4));
+        assert_eq!(game.min_cubes("green"), None);
+        assert_eq!(game.game_power(&COLOURS, MissingColour::Zero), 0);
+        assert_eq!(game.game_power(&COLOURS, MissingColour::One), 24);
+
+        let game = "Game 2: 1 green, 1 red, 1 blue".parse::<Game>().unwrap();
+        assert_eq!(game.game_power(&COLOURS, MissingColour::Zero), 1);
+    }
+
+    #[test]
+    fn it_handles_games_without_sets() {
+        let game = "Game 3:".parse::<Game>().unwrap();
+
+        assert!(game.sets.is_empty());
+        assert!(game.min_bag().is_empty());
+        assert!(game.is_possible(&default_bag()));
+        assert_eq!(game.game_power(&COLOURS, MissingColour::Zero), 0);
+        assert_eq!(game.game_power(&COLOURS, MissingColour::One), 1);
+    }
+
+    #[test]
+    fn it_parses_arbitrary_colours() {
+        let game = "Game 7: 3 purple, 1 red; 2 orange, 4 purple"
+            .parse::<Game>()
+            .unwrap();
+        assert_eq!(game.id, 7);
+        assert_eq!(game.sets[0].cubes.get("purple"), Some(&3));
+        assert_eq!(game.sets[1].cubes.get("orange"), Some(&2));
+        assert_eq!(game.sets[1].cubes.get("red"), None);
+
+        let bag = Bag::from([
+            ("red".to_string(), 1),
+            ("purple".to_string(), 4),
+            ("orange".to_string(), 2),
+        ]);
+        assert!(game.is_possible(&bag));
+
+        let bag = Bag::from([("red".to_string(), 1), ("purple".to_string(), 4)]);
+        assert!(!game.is_possible(&bag));
+        assert_eq!(
+            game.game_power(&["purple", "orange"], MissingColour::Zero),
+            8
+        );
+    }
+
+    #[test]
+    fn it_parses_irregular_whitespace_and_case() {
+        let game = "Game\t12 :  3 Blue,4   RED;\t1 red"
+            .parse::<Game>()
+            .unwrap();
+
+        assert_eq!(game.id, 12);
+        assert_eq!(game.sets[0].cubes.get("blue"), Some(&3));
+        assert_eq!(game.sets[0].cubes.get("red"), Some(&4));
+        assert_eq!(game.sets[1].cubes.get("red"), Some(&1));
+        assert_eq!(game.to_string(), "Game 12: 3 blue, 4 red; 1 red");
+    }
+
+    #[test]
+    fn it_round_trips_through_display() {
+        let input = include_str!("assets/day2/input_test");
+
+        for game in parse_games(input).unwrap() {
+            assert_eq!(game.to_string().parse::<Game>().unwrap(), game);
+        }
+
+        let game = "Game 3:".parse::<Game>().unwrap();
+        assert_eq!(game.to_string(), "Game 3:");
+    }
+
+    #[test]
+    fn it_rejects_duplicate_colours() {
+        let result = "Game 1: 1 red, 1 red".parse::<Game>();
+
+        assert_eq!(
+            result,
+            Err(ParseError {
+                kind: ParseErrorKind::DuplicateColour("red".to_string()),
+                line: 1,
+                span: 17..20,
+            })
+        );
+    }
+
+    #[test]
+    fn it_reports_error_spans() {
+        let result = "Game 1: 3 blue 4 red".parse::<Game>();
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError {
+                kind: ParseErrorKind::Expected("',' or ';'"),
+                line: 1,
+                span: 15..16,
+            }
+        );
+
+        let result = "Game 1: 3 blue,".parse::<Game>();
+        assert_eq!(result.unwrap_err().span, 15..15);
+
+        let result = "Round 1: 3 blue".parse::<Game>();
+        assert_eq!(result.unwrap_err().span, 0..5);
+
+        let result = "Game 1: 3 bl#ue".parse::<Game>();
+        assert_eq!(
+            result.unwrap_err().kind,
+            ParseErrorKind::UnexpectedChar('#')
+        );
+
+        let result = parse_games("Game 1: 1 red\n\nGame 2 1 red");
+        assert_eq!(result.unwrap_err().line, 3);
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn it_calculates_draw_likelihoods() {
+        let bag = parse_bag("red=2,blue=1").unwrap();
+
+        let game = "Game 1: 1 red; 1 red, 1 blue".parse::<Game>().unwrap();
+        assert_close(game.sets[0].probability(&bag), 2.0 / 3.0);
+        assert_close(game.sets[1].probability(&bag), 2.0 / 3.0);
+        assert_close(game.likelihood(&bag), 4.0 / 9.0);
+
+        let game = "Game 2: 3 red".parse::<Game>().unwrap();
+        assert_close(game.likelihood(&bag), 0.0);
+
+        let game = "Game 3: 1 green".parse::<Game>().unwrap();
+        assert_close(game.likelihood(&bag), 0.0);
+    }
+
+    #[test]
+    fn it_finds_the_maximum_likelihood_bag() {
+        let game = "Game 1: 2 red; 1 red, 1 blue".parse::<Game>().unwrap();
+
+        let (bag, likelihood) = game.max_likelihood_bag(&["red", "blue"], 4).unwrap();
+        assert_eq!(bag, parse_bag("red=3,blue=1").unwrap());
+        assert_close(likelihood, 0.25);
+
+        assert!(game.max_likelihood_bag(&["red", "blue"], 2).is_none());
+
+        // an unseen colour only dilutes the bag
+        let (bag, _) = game.max_likelihood_bag(&["green"], 4).unwrap();
+        assert_eq!(bag.get("green"), Some(&0));
+    }
+
+    #[test]
+    fn it_calculates_the_probability_of_a_possible_game() {
+        let bag = parse_bag("red=2,blue=1").unwrap();
+        let limits = parse_bag("red=1,blue=1").unwrap();
+
+        let game = "Game 1: 1 red, 1 blue".parse::<Game>().unwrap();
+        assert_close(game.possible_probability(&bag, &limits), 2.0 / 3.0);
+
+        let game = "Game 2: 1 red, 1 blue; 2 red".parse::<Game>().unwrap();
+        assert_close(game.possible_probability(&bag, &limits), 4.0 / 9.0);
+        assert_close(game.possible_probability(&bag, &bag), 1.0);
+
+        let game = "Game 3: 4 red".parse::<Game>().unwrap();
+        assert_close(game.possible_probability(&bag, &bag), 0.0);
+    }
+}
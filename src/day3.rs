@@ -1,77 +1,536 @@
 // THIS IS A PORT OF https://gitlab.dracoon.com/pioneer/advent-of-code/vm/-/blob/main/src/main/kotlin/day/three/GearRatios.kt
 // originally solved by VM
 
+use std::collections::HashMap;
+use std::ops::Range;
+use std::str::FromStr;
+
 fn main() {
-    let input = include_str!("assets/day3/input_test");
+    let input = include_str!("assets/day3/input");
 
-    let matrix = input
-        .lines()
-        .map(|row| row.chars().collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+    let schematic = match Schematic::try_from(input) {
+        Ok(schematic) => schematic,
+        Err(err) => return eprintln!("{}", err),
+    };
 
-    let mut numbers: Vec<(usize, Vec<char>)> = Vec::new();
+    // the input was validated above, so the parts can't fail
+    println!("Result is {}", solve_part1(input).unwrap());
+    println!("Sum of gear ratios is {}", solve_part2(input).unwrap());
 
-    matrix.iter().enumerate().for_each(|(row_idx, row)| {
-        let mut num_str = String::new();
+    // custom rules, e.g. --numbers adjacent-to=#,distinct=2 or --symbols is=*,neighbours=3
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
 
-        for (col_idx, val) in row.iter().enumerate() {
-            if val.is_numeric() {
-                num_str.push(*val);
+    for pair in args.windows(2) {
+        match pair[0].as_str() {
+            "--numbers" => {
+                let rule = pair[1].parse::<NumberRule>().expect("invalid number rule");
+                println!(
+                    "Sum of numbers matching {} is {}",
+                    pair[1],
+                    schematic.sum_numbers(&rule)
+                );
+            }
+            "--symbols" => {
+                let rule = pair[1].parse::<SymbolRule>().expect("invalid symbol rule");
+                println!(
+                    "Sum of ratios matching {} is {}",
+                    pair[1],
+                    schematic.sum_ratios(&rule)
+                );
             }
+            _ => (),
+        }
+    }
+}
 
-            if !val.is_numeric() || col_idx == row.len() - 1 || !row[col_idx + 1].is_numeric() {
-                if !num_str.is_empty() {
-                    let num = num_str.parse::<usize>().unwrap();
-                    let num_len = num_str.len();
-                    num_str.clear();
+// a number in the schematic and the columns it covers in its row
+#[derive(Debug, PartialEq)]
+struct PartNumber {
+    value: usize,
+    row: usize,
+    cols: Range<usize>,
+}
 
-                    let mut neighbors = Vec::new();
+impl PartNumber {
+    // all cells of the bounding box around the number, clipped at the top and left edges
+    fn neighbourhood(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.row.saturating_sub(1)..=self.row + 1).flat_map(move |row| {
+            (self.cols.start.saturating_sub(1)..=self.cols.end)
+                .filter(move |col| row != self.row || !self.cols.contains(col))
+                .map(move |col| (row, col))
+        })
+    }
+}
 
-                    // Right neighbor
-                    if col_idx < row.len() - 1 && row[col_idx + 1].is_numeric() {
-                        neighbors.push(row[col_idx + num_len]);
-                    } else {
-                        neighbors.push('.');
-                    }
+#[derive(Debug, PartialEq)]
+struct Symbol {
+    value: char,
+    row: usize,
+    col: usize,
+}
 
-                    // Left neighbor
-                    if col_idx >= num_len {
-                        neighbors.push(row[col_idx - num_len]);
-                    } else {
-                        neighbors.push('.');
-                    }
+impl Symbol {
+    // the eight surrounding cells, clipped at the top and left edges
+    fn neighbourhood(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.row.saturating_sub(1)..=self.row + 1).flat_map(move |row| {
+            (self.col.saturating_sub(1)..=self.col + 1)
+                .filter(move |col| (row, *col) != (self.row, self.col))
+                .map(move |col| (row, col))
+        })
+    }
+}
 
-                    // Top neighbors
-                    if row_idx > 0 {
-                        for offset in col_idx + 1 - num_len..=col_idx {
-                            neighbors.push(matrix[row_idx - 1].get(offset).copied().unwrap_or('.'));
-                        }
-                    }
+fn is_symbol(c: char) -> bool {
+    c != '.' && c.is_ascii_punctuation()
+}
 
-                    // Bottom neighbors
-                    if row_idx < matrix.len() - 1 {
-                        for offset in col_idx + 1 - num_len..=col_idx {
-                            neighbors.push(matrix[row_idx + 1].get(offset).copied().unwrap_or('.'));
-                        }
+// a number with more digits than fit into usize, at its first digit
+#[derive(Debug, PartialEq)]
+struct NumberTooLarge {
+    row: usize,
+    col: usize,
+}
+
+impl std::fmt::Display for NumberTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: number exceeds {}",
+            self.row + 1,
+            self.col + 1,
+            usize::MAX
+        )
+    }
+}
+
+struct Schematic {
+    numbers: Vec<PartNumber>,
+    symbols: Vec<Symbol>,
+    // (row, col) -> index into numbers for every digit cell
+    number_index: HashMap<(usize, usize), usize>,
+    // (row, col) -> index into symbols
+    symbol_index: HashMap<(usize, usize), usize>,
+}
+
+impl Schematic {
+    // cells that are neither digits nor matched by `is_symbol` are treated as empty
+    fn with_symbols(
+        input: &str,
+        is_symbol: impl Fn(char) -> bool,
+    ) -> Result<Schematic, NumberTooLarge> {
+        let mut schematic = Schematic {
+            numbers: Vec::new(),
+            symbols: Vec::new(),
+            number_index: HashMap::new(),
+            symbol_index: HashMap::new(),
+        };
+
+        for (row_idx, line) in input.lines().enumerate() {
+            let row = line.chars().collect::<Vec<_>>();
+            let mut col_idx = 0;
+
+            while col_idx < row.len() {
+                let val = row[col_idx];
+
+                if !val.is_ascii_digit() {
+                    if is_symbol(val) {
+                        schematic
+                            .symbol_index
+                            .insert((row_idx, col_idx), schematic.symbols.len());
+                        schematic.symbols.push(Symbol {
+                            value: val,
+                            row: row_idx,
+                            col: col_idx,
+                        });
                     }
+                    col_idx += 1;
+                    continue;
+                }
 
-                    numbers.push((num, neighbors));
+                let start = col_idx;
+                while col_idx < row.len() && row[col_idx].is_ascii_digit() {
+                    schematic
+                        .number_index
+                        .insert((row_idx, col_idx), schematic.numbers.len());
+                    col_idx += 1;
                 }
+
+                let value = row[start..col_idx]
+                    .iter()
+                    .collect::<String>()
+                    .parse::<usize>()
+                    .map_err(|_| NumberTooLarge {
+                        row: row_idx,
+                        col: start,
+                    })?;
+
+                schematic.numbers.push(PartNumber {
+                    value,
+                    row: row_idx,
+                    cols: start..col_idx,
+                });
             }
         }
-    });
 
-    let mut sum = 0;
+        Ok(schematic)
+    }
 
-    numbers.iter().for_each(|(num, neighbors)| {
-        if neighbors
-            .iter()
-            .any(|c| *c != '.' && c.is_ascii_punctuation())
-        {
-            sum += num
+    fn numbers_adjacent_to(&self, symbol: &Symbol) -> Vec<&PartNumber> {
+        let mut indices = symbol
+            .neighbourhood()
+            .filter_map(|cell| self.number_index.get(&cell).copied())
+            .collect::<Vec<_>>();
+        indices.sort();
+        indices.dedup();
+
+        indices.iter().map(|index| &self.numbers[*index]).collect()
+    }
+
+    fn symbols_adjacent_to(&self, number: &PartNumber) -> Vec<&Symbol> {
+        number
+            .neighbourhood()
+            .filter_map(|cell| self.symbol_index.get(&cell))
+            .map(|index| &self.symbols[*index])
+            .collect()
+    }
+}
+
+impl TryFrom<&str> for Schematic {
+    type Error = NumberTooLarge;
+
+    fn try_from(input: &str) -> Result<Schematic, NumberTooLarge> {
+        Schematic::with_symbols(input, is_symbol)
+    }
+}
+
+// rules selecting numbers by the symbols around them
+enum NumberRule {
+    // adjacent to at least one symbol
+    AdjacentToSymbol,
+    // adjacent to the given symbol
+    AdjacentTo(char),
+    // adjacent to at least this many different symbol chars
+    DistinctSymbols(usize),
+    All(Vec<NumberRule>),
+}
+
+// rules selecting symbols by their value and the numbers around them
+enum SymbolRule {
+    Is(char),
+    // adjacent to exactly this many numbers
+    Neighbours(usize),
+    All(Vec<SymbolRule>),
+}
+
+// comma separated rules that all have to match: symbol, adjacent-to=<char>, distinct=<count>
+impl FromStr for NumberRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rules = s
+            .split(',')
+            .map(|rule| match rule.trim().split_once('=') {
+                None if rule.trim() == "symbol" => Ok(NumberRule::AdjacentToSymbol),
+                Some(("adjacent-to", value)) => value
+                    .parse::<char>()
+                    .map(NumberRule::AdjacentTo)
+                    .map_err(|_| format!("invalid symbol: {}", value)),
+                Some(("distinct", count)) => count
+                    .parse::<usize>()
+                    .map(NumberRule::DistinctSymbols)
+                    .map_err(|_| format!("invalid count: {}", count)),
+                _ => Err(format!("unknown number rule: {}", rule)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(NumberRule::All(rules))
+    }
+}
+
+// comma separated rules that all have to match: is=<char>, neighbours=<count>
+impl FromStr for SymbolRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rules = s
+            .split(',')
+            .map(|rule| match rule.trim().split_once('=') {
+                Some(("is", value)) => value
+                    .parse::<char>()
+                    .map(SymbolRule::Is)
+                    .map_err(|_| format!("invalid symbol: {}", value)),
+                Some(("neighbours", count)) => count
+                    .parse::<usize>()
+                    .map(SymbolRule::Neighbours)
+                    .map_err(|_| format!("invalid count: {}", count)),
+                _ => Err(format!("unknown symbol rule: {}", rule)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SymbolRule::All(rules))
+    }
+}
+
+impl Schematic {
+    fn matches_number(&self, number: &PartNumber, rule: &NumberRule) -> bool {
+        match rule {
+            NumberRule::AdjacentToSymbol => !self.symbols_adjacent_to(number).is_empty(),
+            NumberRule::AdjacentTo(value) => self
+                .symbols_adjacent_to(number)
+                .iter()
+                .any(|symbol| symbol.value == *value),
+            NumberRule::DistinctSymbols(count) => {
+                let mut values = self
+                    .symbols_adjacent_to(number)
+                    .iter()
+                    .map(|symbol| symbol.value)
+                    .collect::<Vec<_>>();
+                values.sort();
+                values.dedup();
+
+                values.len() >= *count
+            }
+            NumberRule::All(rules) => rules.iter().all(|rule| self.matches_number(number, rule)),
         }
-    });
+    }
+
+    fn matches_symbol(&self, symbol: &Symbol, rule: &SymbolRule) -> bool {
+        match rule {
+            SymbolRule::Is(value) => symbol.value == *value,
+            SymbolRule::Neighbours(count) => self.numbers_adjacent_to(symbol).len() == *count,
+            SymbolRule::All(rules) => rules.iter().all(|rule| self.matches_symbol(symbol, rule)),
+        }
+    }
+
+    fn numbers_matching(&self, rule: &NumberRule) -> Vec<&PartNumber> {
+        self.numbers
+            .iter()
+            .filter(|number| self.matches_number(number, rule))
+            .collect()
+    }
+
+    fn symbols_matching(&self, rule: &SymbolRule) -> Vec<&Symbol> {
+        self.symbols
+            .iter()
+            .filter(|symbol| self.matches_symbol(symbol, rule))
+            .collect()
+    }
+
+    fn sum_numbers(&self, rule: &NumberRule) -> usize {
+        self.numbers_matching(rule)
+            .iter()
+            .map(|number| number.value)
+            .sum()
+    }
+
+    // sum over the matching symbols of the product of their adjacent numbers
+    fn sum_ratios(&self, rule: &SymbolRule) -> usize {
+        self.symbols_matching(rule)
+            .iter()
+            .map(|symbol| {
+                self.numbers_adjacent_to(symbol)
+                    .iter()
+                    .map(|number| number.value)
+                    .product::<usize>()
+            })
+            .sum()
+    }
+}
+
+fn solve_part1(input: &str) -> Result<usize, NumberTooLarge> {
+    Ok(Schematic::try_from(input)?.sum_numbers(&NumberRule::AdjacentToSymbol))
+}
+
+// gears are '*' next to exactly two numbers
+fn solve_part2(input: &str) -> Result<usize, NumberTooLarge> {
+    Ok(
+        Schematic::try_from(input)?.sum_ratios(&SymbolRule::All(vec![
+            SymbolRule::Is('*'),
+            SymbolRule::Neighbours(2),
+        ])),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part1_works() {
+        // the example with 755 in place of 592 in the seventh row
+        let input = include_str!("assets/day3/input_test");
+        assert_eq!(solve_part1(input), Ok(4361 - 592 + 755));
+    }
+
+    #[test]
+    fn part1_counts_diagonal_symbols() {
+        let input = "*....\n.12..\n.....\n...3.\n....#";
+        assert_eq!(solve_part1(input), Ok(12 + 3));
+
+        let input = "....*\n.12..\n.....";
+        assert_eq!(solve_part1(input), Ok(0));
+    }
+
+    #[test]
+    fn part1_handles_numbers_at_row_ends() {
+        let input = "12$\n..5\n...\n7..";
+        assert_eq!(solve_part1(input), Ok(12 + 5));
+
+        let input = "..34\n+...\n....\n..9/";
+        assert_eq!(solve_part1(input), Ok(9));
+    }
+
+    #[test]
+    fn numbers_too_large_for_usize_are_rejected() {
+        let max = usize::MAX.to_string();
+        let schematic = Schematic::try_from(format!("*{}", max).as_str()).unwrap();
+        assert_eq!(schematic.numbers[0].value, usize::MAX);
+
+        let input = format!("..\n#.{}0", max);
+        assert_eq!(
+            Schematic::try_from(input.as_str()).err(),
+            Some(NumberTooLarge { row: 1, col: 2 })
+        );
+        assert_eq!(solve_part1(&input), Err(NumberTooLarge { row: 1, col: 2 }));
+    }
+
+    #[test]
+    fn neighbourhoods_are_clipped_at_the_edges() {
+        let schematic = Schematic::try_from("12.\n...").unwrap();
+
+        assert_eq!(schematic.numbers.len(), 1);
+        assert_eq!(
+            schematic.numbers[0].neighbourhood().collect::<Vec<_>>(),
+            vec![(0, 2), (1, 0), (1, 1), (1, 2)]
+        );
+
+        let schematic = Schematic::try_from("#").unwrap();
+        assert_eq!(
+            schematic.symbols[0].neighbourhood().collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn schematic_indexes_numbers_and_symbols() {
+        let input = include_str!("assets/day3/input_test");
+        let schematic = Schematic::try_from(input).unwrap();
+
+        assert_eq!(schematic.numbers.len(), 10);
+        assert_eq!(schematic.symbols.len(), 6);
+        assert_eq!(
+            schematic.numbers[0],
+            PartNumber {
+                value: 467,
+                row: 0,
+                cols: 0..3,
+            }
+        );
+        assert_eq!(
+            schematic.symbols[0],
+            Symbol {
+                value: '*',
+                row: 1,
+                col: 3,
+            }
+        );
+
+        let adjacent = schematic.numbers_adjacent_to(&schematic.symbols[0]);
+        assert_eq!(
+            adjacent
+                .iter()
+                .map(|number| number.value)
+                .collect::<Vec<_>>(),
+            vec![467, 35]
+        );
+
+        let adjacent = schematic.symbols_adjacent_to(&schematic.numbers[2]);
+        assert_eq!(adjacent, vec![&schematic.symbols[0]]);
+        assert!(schematic
+            .symbols_adjacent_to(&schematic.numbers[1])
+            .is_empty());
+    }
+
+    #[test]
+    fn schematic_uses_custom_symbol_predicate() {
+        let input = include_str!("assets/day3/input_test");
+        let schematic = Schematic::with_symbols(input, |c| c == '*').unwrap();
+
+        assert_eq!(schematic.symbols.len(), 3);
+        assert!(schematic.symbols.iter().all(|symbol| symbol.value == '*'));
+        assert!(schematic
+            .symbols_adjacent_to(&schematic.numbers[3])
+            .is_empty());
+    }
+
+    #[test]
+    fn part2_works() {
+        let input = include_str!("assets/day3/input_test");
+        assert_eq!(solve_part2(input), Ok(467835));
+    }
+
+    #[test]
+    fn part2_ignores_stars_without_two_numbers() {
+        let input = "12*..\n..*3.\n4*5*6";
+        // first star touches 12 and 3, second 12, 3 and 5, third 4 and 5, last 3, 5 and 6
+        assert_eq!(solve_part2(input), Ok(12 * 3 + 4 * 5));
+    }
+
+    #[test]
+    fn rules_select_numbers_by_symbol() {
+        let input = include_str!("assets/day3/input_test");
+        let schematic = Schematic::try_from(input).unwrap();
+
+        assert_eq!(
+            schematic.sum_numbers(&NumberRule::AdjacentTo('*')),
+            467 + 35 + 617 + 755 + 598
+        );
+        assert_eq!(schematic.sum_numbers(&NumberRule::AdjacentTo('#')), 633);
+        assert_eq!(schematic.sum_numbers(&NumberRule::DistinctSymbols(2)), 0);
+
+        let schematic = Schematic::try_from("1.2\n*3#").unwrap();
+        let numbers = schematic.numbers_matching(&NumberRule::DistinctSymbols(2));
+        assert_eq!(numbers, vec![&schematic.numbers[2]]);
+
+        let rule = NumberRule::All(vec![
+            NumberRule::AdjacentTo('#'),
+            NumberRule::AdjacentToSymbol,
+        ]);
+        assert_eq!(schematic.sum_numbers(&rule), 2 + 3);
+    }
+
+    #[test]
+    fn rules_select_gears_by_neighbour_count() {
+        let input = include_str!("assets/day3/input_test");
+        let schematic = Schematic::try_from(input).unwrap();
+
+        let rule = SymbolRule::All(vec![SymbolRule::Is('*'), SymbolRule::Neighbours(1)]);
+        assert_eq!(schematic.symbols_matching(&rule).len(), 1);
+        assert_eq!(schematic.sum_ratios(&rule), 617);
+
+        assert_eq!(schematic.sum_ratios(&SymbolRule::Neighbours(2)), 467835);
+        assert_eq!(
+            schematic.symbols_matching(&SymbolRule::Neighbours(3)).len(),
+            0
+        );
+    }
+
+    #[test]
+    fn rules_parse_from_text() {
+        let input = include_str!("assets/day3/input_test");
+        let schematic = Schematic::try_from(input).unwrap();
+
+        let rule = "adjacent-to=*".parse::<NumberRule>().unwrap();
+        assert_eq!(schematic.sum_numbers(&rule), 467 + 35 + 617 + 755 + 598);
+
+        let rule = "symbol,distinct=1".parse::<NumberRule>().unwrap();
+        assert_eq!(schematic.sum_numbers(&rule), 4361 - 592 + 755);
 
-    println!("Result is {}", sum);
+        let rule = "is=*,neighbours=2".parse::<SymbolRule>().unwrap();
+        assert_eq!(schematic.sum_ratios(&rule), 467835);
 
+        assert!("adjacent-to=**".parse::<NumberRule>().is_err());
+        assert!("neighbours=two".parse::<SymbolRule>().is_err());
+        assert!("is".parse::<SymbolRule>().is_err());
+    }
 }
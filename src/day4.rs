@@ -1,156 +1,256 @@
 fn main() {
-    // PART 2
-
     let input = include_str!("assets/day4/input");
-    // remove first 8 chars from line and split to lines
-    let input = input
-        .lines()
-        .map(|line| line.chars().skip(7).collect::<String>())
-        .collect::<Vec<_>>()
-        .join("\n");
 
-    let game = input.into_game();
+    match input.into_game() {
+        Ok(game) => {
+            match score_cards(&game) {
+                Ok(points) => println!("Points: {}", points),
+                Err(err) => eprintln!("{}", err),
+            }
 
-    let mut winning_cards = Vec::new();
-    for (card_index, _) in game.own.iter().enumerate() {
-        process_card(&game, card_index, &mut winning_cards);
+            match count_cards_with_copies(&game) {
+                Ok(total) => println!("Cards including copies: {}", total),
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        Err(err) => eprintln!("{}", err),
     }
+}
 
-    println!("Result is {}", winning_cards.len());
+// a card is worth 1 point for its first match and doubles for every further one
+fn score_cards(game: &Game) -> Result<u64, OverflowError> {
+    game.matching_counts()
+        .iter()
+        .zip(game.ids.iter())
+        .filter(|(count, _)| **count > 0)
+        .try_fold(0u64, |total, (count, id)| {
+            1u64.checked_shl(*count as u32 - 1)
+                .and_then(|points| total.checked_add(points))
+                .ok_or(OverflowError { id: *id })
+        })
 }
 
-fn process_card(game: &Game, card_index: usize, winning_cards: &mut Vec<Card>) {
-    if card_index >= game.own.len() {
-        return;
+// counts all cards including won copies, each card only adds its number of copies
+// to the cards it wins instead of processing every copy on its own
+fn count_cards_with_copies(game: &Game) -> Result<u64, OverflowError> {
+    let counts = game.matching_counts();
+    let mut copies = vec![1u64; counts.len()];
+
+    for (card_index, count) in counts.iter().enumerate() {
+        let won = card_index + 1..(card_index + 1 + count).min(copies.len());
+        for index in won {
+            copies[index] = copies[index]
+                .checked_add(copies[card_index])
+                .ok_or(OverflowError {
+                    id: game.ids[index],
+                })?;
+        }
     }
 
-    let card = &game.own[card_index];
-    let count_winning_numbers = game
-        .winners
-        .get(card_index)
-        .unwrap()
+    copies
         .iter()
-        .filter(|num| card.contains(num))
-        .count();
+        .zip(game.ids.iter())
+        .try_fold(0u64, |total, (copies, id)| {
+            total.checked_add(*copies).ok_or(OverflowError { id: *id })
+        })
+}
 
-    // Add the current card
-    winning_cards.push(card.clone());
+// totals grow exponentially with the deck, reports the card at which u64 overflowed
+#[derive(Debug, PartialEq)]
+struct OverflowError {
+    id: u32,
+}
 
-    // Recursively process the next cards based on count of winning numbers
-    for index in 1..=count_winning_numbers {
-        process_card(game, card_index + index, winning_cards);
+impl std::fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "card {}: total exceeds {}", self.id, u64::MAX)
     }
 }
 
-#[allow(dead_code)]
-fn solve_part1() {
-    let input = include_str!("assets/day4/input");
-
-    // remove first 8 chars from line and split to lines
-    let input = input
-        .lines()
-        .map(|line| line.chars().skip(7).collect::<String>())
-        .collect::<Vec<_>>()
-        .join("\n");
+// card numbers have to be in 0..=MAX_NUMBER to fit into the bitset
+const MAX_NUMBER: u16 = 255;
 
-    let game = input.into_game();
+// set of card numbers as a fixed-size bitset
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Card([u64; 4]);
 
-    let mut points = 0;
+impl Card {
+    // returns false if the number was already present
+    fn insert(&mut self, number: u16) -> bool {
+        assert!(number <= MAX_NUMBER, "card number out of range");
 
-    for (card_index, card) in game.own.iter().enumerate() {
-        let count_winning_numbers = game
-            .winners
-            .get(card_index)
-            .unwrap()
-            .iter()
-            .filter(|num| card.contains(num))
-            .count();
-
-        println!("winning numbers: {}", count_winning_numbers);
-
-        let mut card_points = 0;
+        let (word, bit) = (number as usize / 64, number % 64);
+        let present = self.0[word] & (1 << bit) != 0;
+        self.0[word] |= 1 << bit;
 
-        if count_winning_numbers > 0 {
-            card_points += 1;
-        }
+        !present
+    }
 
-        for _ in 1..count_winning_numbers {
-            card_points *= 2;
+    fn intersection(&self, other: &Card) -> Card {
+        let mut words = self.0;
+        for (word, other) in words.iter_mut().zip(other.0.iter()) {
+            *word &= other;
         }
 
-        points += card_points;
+        Card(words)
+    }
 
-        println!("Card {} wins {} points", card_index, card_points);
+    fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
     }
 
-    println!("Result is {}", points);
+    fn numbers(&self) -> impl Iterator<Item = u16> + '_ {
+        (0..=MAX_NUMBER).filter(|number| self.0[*number as usize / 64] & (1 << (number % 64)) != 0)
+    }
 }
 
-type Card = Vec<u16>;
+impl std::fmt::Debug for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.numbers()).finish()
+    }
+}
 
 struct Game {
+    ids: Vec<u32>,
     winners: Vec<Card>,
     own: Vec<Card>,
 }
 
+impl Game {
+    // own numbers that are winners, per card
+    fn matches(&self) -> Vec<Card> {
+        self.winners
+            .iter()
+            .zip(self.own.iter())
+            .map(|(winners, card)| winners.intersection(card))
+            .collect()
+    }
+
+    fn matching_counts(&self) -> Vec<usize> {
+        self.matches().iter().map(|card| card.len()).collect()
+    }
+}
+
+// line numbers are 1-based
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    MissingPrefix { line: usize },
+    InvalidId { line: usize, value: String },
+    MissingSeparator { line: usize },
+    InvalidNumber { line: usize, value: String },
+    DuplicateNumber { line: usize, number: u16 },
+    NumberOutOfRange { line: usize, number: u16 },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::MissingPrefix { line } => {
+                write!(f, "line {}: expected 'Card <id>:'", line)
+            }
+            ParseError::InvalidId { line, value } => {
+                write!(f, "line {}: invalid card id {:?}", line, value)
+            }
+            ParseError::MissingSeparator { line } => {
+                write!(f, "line {}: expected exactly one '|'", line)
+            }
+            ParseError::InvalidNumber { line, value } => {
+                write!(f, "line {}: invalid number {:?}", line, value)
+            }
+            ParseError::DuplicateNumber { line, number } => {
+                write!(f, "line {}: duplicate number {}", line, number)
+            }
+            ParseError::NumberOutOfRange { line, number } => {
+                write!(f, "line {}: number {} exceeds {}", line, number, MAX_NUMBER)
+            }
+        }
+    }
+}
+
 trait IntoGame {
-    fn into_game(self) -> Game;
+    fn into_game(self) -> Result<Game, ParseError>;
+}
+
+fn parse_numbers(numbers: &str, line: usize) -> Result<Card, ParseError> {
+    let mut card = Card::default();
+
+    for value in numbers.split_whitespace() {
+        let number = value
+            .parse::<u16>()
+            .map_err(|_| ParseError::InvalidNumber {
+                line,
+                value: value.to_string(),
+            })?;
+
+        if number > MAX_NUMBER {
+            return Err(ParseError::NumberOutOfRange { line, number });
+        }
+
+        if !card.insert(number) {
+            return Err(ParseError::DuplicateNumber { line, number });
+        }
+    }
+
+    Ok(card)
 }
 
+// parses lines of the form "Card <id>: <winners> | <own>", blank lines are skipped
 impl IntoGame for &str {
-    fn into_game(self) -> Game {
-        self.lines().fold(
-            Game {
-                winners: Vec::new(),
-                own: Vec::new(),
-            },
-            |mut game, line| {
-                if line.is_empty() {
-                    return game;
-                }
-
-                let cards: Vec<_> = line.split('|').collect();
-
-                let winners = cards
-                    .first()
-                    .unwrap()
-                    .split(' ')
-                    .filter(|num| !num.is_empty() && num.chars().all(|c| c.is_numeric()))
-                    .map(|card| card.parse::<u16>().unwrap())
-                    .collect::<Vec<_>>();
-                let own = cards
-                    .get(1)
-                    .unwrap()
-                    .split(' ')
-                    .filter(|num| !num.is_empty())
-                    .map(|card| card.parse::<u16>().unwrap())
-                    .collect::<Vec<_>>();
-
-                game.winners.push(winners);
-                game.own.push(own);
-
-                game
-            },
-        )
+    fn into_game(self) -> Result<Game, ParseError> {
+        let mut game = Game {
+            ids: Vec::new(),
+            winners: Vec::new(),
+            own: Vec::new(),
+        };
+
+        for (index, line) in self.lines().enumerate() {
+            let line_number = index + 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (prefix, numbers) = line
+                .split_once(':')
+                .ok_or(ParseError::MissingPrefix { line: line_number })?;
+
+            let id = match prefix.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["Card", id] => id.parse::<u32>().map_err(|_| ParseError::InvalidId {
+                    line: line_number,
+                    value: id.to_string(),
+                })?,
+                _ => return Err(ParseError::MissingPrefix { line: line_number }),
+            };
+
+            let (winners, own) = match numbers.split('|').collect::<Vec<_>>().as_slice() {
+                [winners, own] => (
+                    parse_numbers(winners, line_number)?,
+                    parse_numbers(own, line_number)?,
+                ),
+                _ => return Err(ParseError::MissingSeparator { line: line_number }),
+            };
+
+            game.ids.push(id);
+            game.winners.push(winners);
+            game.own.push(own);
+        }
+
+        Ok(game)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{process_card, IntoGame};
+    use crate::{
+        count_cards_with_copies, score_cards, Card, IntoGame, OverflowError, ParseError, MAX_NUMBER,
+    };
 
     #[test]
     fn it_parses_correctly_to_game() {
         let input = include_str!("assets/day4/input_test");
-        // remove first 8 chars from line and split to lines
-        let input = input
-            .lines()
-            .map(|line| line.chars().skip(7).collect::<String>())
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        let game = input.into_game();
+        let game = input.into_game().unwrap();
 
+        assert_eq!(game.ids, vec![1, 2, 3, 4, 5, 6]);
         assert_eq!(game.winners.len(), 6);
         assert_eq!(game.own.len(), 6);
 
@@ -162,61 +262,175 @@ mod tests {
     fn it_works_correctly() {
         let input = include_str!("assets/day4/input_test");
 
-        // remove first 8 chars from line and split to lines
-        let input = input
-            .lines()
-            .map(|line| line.chars().skip(7).collect::<String>())
-            .collect::<Vec<_>>()
-            .join("\n");
+        let game = input.into_game().unwrap();
 
-        let game = input.into_game();
+        assert_eq!(game.matching_counts(), vec![4, 2, 2, 1, 0, 0]);
+        assert_eq!(score_cards(&game), Ok(13));
+    }
 
-        let mut points = 0;
+    #[test]
+    fn it_works_correctly_again() {
+        let input = include_str!("assets/day4/input_test");
 
-        for (card_index, card) in game.own.iter().enumerate() {
-            let count_winning_numbers = game
-                .winners
-                .get(card_index)
-                .unwrap()
-                .iter()
-                .filter(|num| card.contains(num))
-                .count();
+        let game = input.into_game().unwrap();
 
-            let mut card_points = 0;
+        assert_eq!(count_cards_with_copies(&game), Ok(30));
+    }
 
-            if count_winning_numbers > 0 {
-                card_points += 1;
-            }
+    #[test]
+    fn it_counts_copies_past_the_last_card() {
+        // every card wins two cards, copies beyond the table are dropped
+        let input = "Card 1: 1 2 | 1 2\nCard 2: 1 2 | 1 2\nCard 3: 1 2 | 1 2";
+        let game = input.into_game().unwrap();
 
-            for _ in 1..count_winning_numbers {
-                card_points *= 2;
-            }
+        assert_eq!(count_cards_with_copies(&game), Ok(1 + 2 + 4));
+    }
 
-            points += card_points;
-        }
+    // every card matches 64 numbers, so card n has 2^(n - 1) copies
+    fn doubling_deck(cards: u32) -> String {
+        let numbers = (1..=64)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
 
-        assert_eq!(points, 13);
+        (1..=cards)
+            .map(|id| format!("Card {}: {} | {}", id, numbers, numbers))
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
     #[test]
-    fn it_works_correctly_again() {
-        let input = include_str!("assets/day4/input_test");
+    fn it_reports_overflowing_copy_counts() {
+        let game = doubling_deck(64).as_str().into_game().unwrap();
+        assert_eq!(count_cards_with_copies(&game), Ok(u64::MAX));
+
+        let game = doubling_deck(65).as_str().into_game().unwrap();
+        assert_eq!(
+            count_cards_with_copies(&game),
+            Err(OverflowError { id: 65 })
+        );
+    }
+
+    #[test]
+    fn it_reports_overflowing_points() {
+        let game = doubling_deck(1).as_str().into_game().unwrap();
+        assert_eq!(score_cards(&game), Ok(1 << 63));
 
-        // remove first 8 chars from line and split to lines
-        let input = input
-            .lines()
-            .map(|line| line.chars().skip(7).collect::<String>())
+        // two cards worth 2^63 each no longer fit
+        let game = doubling_deck(2).as_str().into_game().unwrap();
+        assert_eq!(score_cards(&game), Err(OverflowError { id: 2 }));
+
+        let numbers = (1..=70)
+            .map(|n| n.to_string())
             .collect::<Vec<_>>()
-            .join("\n");
+            .join(" ");
+        let input = format!("Card 7: {} | {}", numbers, numbers);
+        let game = input.as_str().into_game().unwrap();
+        assert_eq!(score_cards(&game), Err(OverflowError { id: 7 }));
+    }
+
+    #[test]
+    fn it_parses_large_card_ids() {
+        let input = "Card 1234: 41 48 | 83 41\nCard\t12345 :1|1";
+        let game = input.into_game().unwrap();
+
+        assert_eq!(game.ids, vec![1234, 12345]);
+        let numbers = |cards: &[Card]| {
+            cards
+                .iter()
+                .map(|card| card.numbers().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(numbers(&game.winners), vec![vec![41, 48], vec![1]]);
+        assert_eq!(numbers(&game.own), vec![vec![41, 83], vec![1]]);
+    }
 
-        let game = input.into_game();
+    #[test]
+    fn it_rejects_invalid_cards() {
+        let result = "Card 1: 41 41 | 83".into_game();
+        assert_eq!(
+            result.err(),
+            Some(ParseError::DuplicateNumber {
+                line: 1,
+                number: 41
+            })
+        );
+
+        let result = "Card 1: 41 | 83\nCard 2: 41 | 83 7 83".into_game();
+        assert_eq!(
+            result.err(),
+            Some(ParseError::DuplicateNumber {
+                line: 2,
+                number: 83
+            })
+        );
+
+        let result = "41 48 | 83 86".into_game();
+        assert_eq!(result.err(), Some(ParseError::MissingPrefix { line: 1 }));
+
+        let result = "Card x: 41 48 | 83 86".into_game();
+        assert_eq!(
+            result.err(),
+            Some(ParseError::InvalidId {
+                line: 1,
+                value: "x".to_string()
+            })
+        );
+
+        let result = "Card 1: 41 48 83 86".into_game();
+        assert_eq!(result.err(), Some(ParseError::MissingSeparator { line: 1 }));
+
+        let result = "Card 1: 41 48 | 83 256".into_game();
+        assert_eq!(
+            result.err(),
+            Some(ParseError::NumberOutOfRange {
+                line: 1,
+                number: 256
+            })
+        );
+
+        let result = "Card 1: 41 -48 | 83 86".into_game();
+        assert_eq!(
+            result.err(),
+            Some(ParseError::InvalidNumber {
+                line: 1,
+                value: "-48".to_string()
+            })
+        );
+    }
 
-        let mut winning_cards = Vec::new();
+    #[test]
+    fn it_intersects_card_bitsets() {
+        let mut winners = Card::default();
+        let mut own = Card::default();
 
-        for (card_index, _) in game.own.iter().enumerate() {
-            process_card(&game, card_index, &mut winning_cards);
+        for number in [0, 17, 64, 100, MAX_NUMBER] {
+            assert!(winners.insert(number));
         }
+        assert!(!winners.insert(64));
 
-        assert_eq!(winning_cards.len(), 30);
+        for number in [17, 63, 64, MAX_NUMBER] {
+            own.insert(number);
+        }
+
+        let matched = winners.intersection(&own);
+        assert_eq!(matched.len(), 3);
+        assert_eq!(
+            matched.numbers().collect::<Vec<_>>(),
+            vec![17, 64, MAX_NUMBER]
+        );
+    }
+
+    #[test]
+    fn it_reports_matched_numbers() {
+        let input = include_str!("assets/day4/input_test");
+        let game = input.into_game().unwrap();
+
+        let matches = game.matches();
+        assert_eq!(
+            matches[0].numbers().collect::<Vec<_>>(),
+            vec![17, 48, 83, 86]
+        );
+        assert_eq!(matches[5].len(), 0);
     }
 }
@@ -1,110 +1,464 @@
-struct Race {
-    distance: u64,
-    duration: u64,
+use std::ops::{Add, Div, Mul, RangeInclusive, Sub};
+use std::str::FromStr;
+
+// integer width used for the kerned race of part 2
+#[cfg(feature = "bigint")]
+type KernedInt = num_bigint::BigUint;
+#[cfg(not(feature = "bigint"))]
+type KernedInt = u128;
+
+trait RaceInt:
+    Clone
+    + Ord
+    + FromStr
+    + From<u64>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+{
+    // None if the product does not fit into the integer width
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    fn isqrt(&self) -> Self;
 }
 
-impl From<(u64, u64)> for Race {
-    fn from((distance, duration): (u64, u64)) -> Self {
-        Self { distance, duration }
+macro_rules! impl_race_int {
+    ($($int:ty),*) => {
+        $(
+            impl RaceInt for $int {
+                fn checked_mul(&self, other: &Self) -> Option<Self> {
+                    <$int>::checked_mul(*self, *other)
+                }
+
+                fn isqrt(&self) -> Self {
+                    <$int>::isqrt(*self)
+                }
+            }
+        )*
+    };
+}
+
+impl_race_int!(u64, u128);
+
+#[cfg(feature = "bigint")]
+impl RaceInt for num_bigint::BigUint {
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn isqrt(&self) -> Self {
+        self.sqrt()
     }
 }
 
-fn solve_part1(races: Vec<Race>) -> u64 {
-    let result = races
+// how holding the button translates into speed, the puzzle's boat gains 1 mm/ms per ms
+// of holding without any limit
+#[derive(Debug, Clone, PartialEq)]
+struct Physics<T> {
+    // speed gained per ms of holding the button
+    acceleration: T,
+    max_speed: Option<T>,
+    // holding for less than this does not move the boat at all
+    min_charge: T,
+}
+
+impl<T: RaceInt> Physics<T> {
+    fn standard() -> Self {
+        Physics {
+            acceleration: T::from(1),
+            max_speed: None,
+            min_charge: T::from(0),
+        }
+    }
+
+    // None if the speed does not fit into the integer width
+    fn speed(&self, hold: &T) -> Option<T> {
+        if *hold < self.min_charge {
+            return Some(T::from(0));
+        }
+
+        match (self.acceleration.checked_mul(hold), &self.max_speed) {
+            (Some(speed), Some(max_speed)) => Some(speed.min(max_speed.clone())),
+            (None, Some(max_speed)) => Some(max_speed.clone()),
+            (speed, None) => speed,
+        }
+    }
+}
+
+struct Race<T> {
+    distance: T,
+    duration: T,
+    physics: Physics<T>,
+}
+
+impl<T: RaceInt> From<(T, T)> for Race<T> {
+    fn from((distance, duration): (T, T)) -> Self {
+        Self {
+            distance,
+            duration,
+            physics: Physics::standard(),
+        }
+    }
+}
+
+impl<T: RaceInt> Race<T> {
+    fn with_physics(self, physics: Physics<T>) -> Self {
+        Self { physics, ..self }
+    }
+
+    // None if the distance does not fit into the integer width
+    fn travelled(&self, hold: &T) -> Option<T> {
+        if *hold >= self.duration {
+            return Some(T::from(0));
+        }
+
+        let remaining = self.duration.clone() - hold.clone();
+        self.physics.speed(hold)?.checked_mul(&remaining)
+    }
+
+    fn wins(&self, hold: &T) -> bool {
+        self.travelled(hold)
+            .is_none_or(|travelled| travelled > self.distance)
+    }
+
+    // lower root of hold * (duration - hold) = distance, may be off by one
+    fn estimate_low(&self) -> Option<T> {
+        let square = self.duration.checked_mul(&self.duration);
+        let four_distance = T::from(4).checked_mul(&self.distance);
+
+        match (square, four_distance) {
+            (Some(square), Some(four_distance)) if square >= four_distance => {
+                Some((self.duration.clone() - (square - four_distance).isqrt()) / T::from(2))
+            }
+            (Some(_), _) => None,
+            // the square does not fit into the integer width, bisect instead
+            (None, _) => {
+                let (mut low, mut high) = (T::from(0), self.duration.clone() / T::from(2));
+
+                while low < high {
+                    let mid = low.clone() + (high.clone() - low.clone()) / T::from(2);
+                    if self.wins(&mid) {
+                        high = mid;
+                    } else {
+                        low = mid + T::from(1);
+                    }
+                }
+
+                Some(low)
+            }
+        }
+    }
+
+    // hold times that beat the record
+    fn winning_holds(&self) -> Option<RangeInclusive<T>> {
+        if self.physics == Physics::standard() {
+            self.standard_winning_holds()
+        } else {
+            self.general_winning_holds()
+        }
+    }
+
+    // closed form solution of hold * (duration - hold) > distance
+    fn standard_winning_holds(&self) -> Option<RangeInclusive<T>> {
+        let one = T::from(1);
+        let half = self.duration.clone() / T::from(2);
+        let mut low = self.estimate_low()?;
+
+        while low > T::from(0) && self.wins(&(low.clone() - one.clone())) {
+            low = low - one.clone();
+        }
+        while low <= half && !self.wins(&low) {
+            low = low + one.clone();
+        }
+
+        // the distance peaks at duration / 2 and is symmetric around it
+        (low <= half).then(|| low.clone()..=self.duration.clone() - low)
+    }
+
+    // the travelled distance is concave on [min_charge, duration] as the minimum of
+    // acceleration * hold * (duration - hold) and max_speed * (duration - hold), so the
+    // winning holds form an interval around the best hold and both ends can be bisected
+    fn general_winning_holds(&self) -> Option<RangeInclusive<T>> {
+        let (zero, one, two) = (T::from(0), T::from(1), T::from(2));
+        let min_charge = self.physics.min_charge.clone();
+
+        // the best hold is next to duration / 2, max_speed / acceleration or min_charge
+        let mut candidates = vec![
+            self.duration.clone() / two.clone(),
+            self.duration.clone() / two.clone() + one.clone(),
+            min_charge.clone(),
+        ];
+        if let Some(max_speed) = &self.physics.max_speed {
+            if self.physics.acceleration > zero {
+                let full_speed = max_speed.clone() / self.physics.acceleration.clone();
+                candidates.push(full_speed.clone());
+                candidates.push(full_speed + one.clone());
+            }
+        }
+
+        // distances that do not fit into the integer width are the longest
+        let best = candidates
+            .into_iter()
+            .filter(|hold| *hold >= min_charge && *hold <= self.duration)
+            .max_by_key(|hold| {
+                let travelled = self.travelled(hold);
+                (travelled.is_none(), travelled)
+            })
+            .filter(|hold| self.wins(hold))?;
+
+        // first winning hold in [min_charge, best]
+        let (mut low, mut high) = (min_charge, best.clone());
+        while low < high {
+            let mid = low.clone() + (high.clone() - low.clone()) / two.clone();
+            if self.wins(&mid) {
+                high = mid;
+            } else {
+                low = mid + one.clone();
+            }
+        }
+        let first = low;
+
+        // last winning hold in [best, duration]
+        let (mut low, mut high) = (best, self.duration.clone());
+        while low < high {
+            let mid = low.clone() + (high.clone() - low.clone() + one.clone()) / two.clone();
+            if self.wins(&mid) {
+                low = mid;
+            } else {
+                high = mid - one.clone();
+            }
+        }
+
+        Some(first..=low)
+    }
+
+    fn ways_to_win(&self) -> T {
+        self.winning_holds()
+            .map(|holds| holds.end().clone() - holds.start().clone() + T::from(1))
+            .unwrap_or(T::from(0))
+    }
+}
+
+fn solve_part1<T: RaceInt>(races: Vec<Race<T>>) -> T {
+    races
         .iter()
-        .map(|race| {
-            (1..=race.duration)
-                .filter(|hold| {
-                    let velocity = *hold;
-
-                    race.distance < (velocity * (race.duration - hold))
-                })
-                .count()
-        })
-        .product::<usize>();
+        .fold(T::from(1), |product, race| product * race.ways_to_win())
+}
 
-    result as u64
+fn solve_part2<T: RaceInt>(race: Race<T>) -> T {
+    race.ways_to_win()
 }
 
-fn solve_part2(race: Race) -> u64 {
-    // faster for larger numbers
+// positions are 1-based line numbers and byte columns
+#[derive(Debug, PartialEq)]
+struct SheetError {
+    line: usize,
+    column: usize,
+    kind: SheetErrorKind,
+}
 
-    let low = (1..race.duration)
-        .find(|hold| {
-            let velocity = *hold as u128;
+#[derive(Debug, PartialEq)]
+enum SheetErrorKind {
+    MissingRow(&'static str),
+    MissingLabel(&'static str),
+    // the row has a label but no numbers after it
+    MissingNumbers(&'static str),
+    InvalidNumber(String),
+    ColumnMismatch { times: usize, distances: usize },
+    // the number does not fit into the requested integer width
+    Overflow(String),
+}
 
-            (race.distance as u128) < (velocity * (race.duration as u128 - *hold as u128))
-        })
-        .unwrap();
+impl std::fmt::Display for SheetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+
+        match &self.kind {
+            SheetErrorKind::MissingRow(label) => write!(f, "missing {} row", label),
+            SheetErrorKind::MissingLabel(label) => write!(f, "expected {}", label),
+            SheetErrorKind::MissingNumbers(label) => write!(f, "no numbers after {}", label),
+            SheetErrorKind::InvalidNumber(value) => write!(f, "invalid number {:?}", value),
+            SheetErrorKind::ColumnMismatch { times, distances } => {
+                write!(f, "{} times but {} distances", times, distances)
+            }
+            SheetErrorKind::Overflow(value) => write!(f, "number {} is too large", value),
+        }
+    }
+}
 
-    let high = (1..race.duration)
-        .rev()
-        .find(|hold| {
-            let velocity = *hold as u128;
+// a number of the sheet and its position
+struct Column<'a> {
+    value: &'a str,
+    line: usize,
+    column: usize,
+}
 
-            (race.distance as u128) < (velocity * (race.duration as u128 - *hold as u128))
+impl Column<'_> {
+    fn parse<T: RaceInt>(&self) -> Result<T, SheetError> {
+        self.value.parse::<T>().map_err(|_| SheetError {
+            line: self.line,
+            column: self.column,
+            kind: SheetErrorKind::Overflow(self.value.to_string()),
         })
-        .unwrap();
+    }
+}
 
-    high - low + 1
+// the Time/Distance sheet, either read as separate races or as one kerned race
+struct RaceSheet<'a> {
+    times: Vec<Column<'a>>,
+    distances: Vec<Column<'a>>,
 }
 
-fn main() {
-    let input = include_str!("assets/day6/input");
+impl<'a> RaceSheet<'a> {
+    fn parse(input: &'a str) -> Result<RaceSheet<'a>, SheetError> {
+        let mut lines = input.lines();
+
+        let times = Self::parse_row(lines.next(), 1, "Time:")?;
+        let distances = Self::parse_row(lines.next(), 2, "Distance:")?;
+
+        if times.len() != distances.len() {
+            let (line, column) = distances
+                .get(times.len())
+                .map(|column| (column.line, column.column))
+                .unwrap_or_else(|| {
+                    let line = input.lines().nth(1).unwrap_or_default();
+                    (2, line.len() + 1)
+                });
+
+            return Err(SheetError {
+                line,
+                column,
+                kind: SheetErrorKind::ColumnMismatch {
+                    times: times.len(),
+                    distances: distances.len(),
+                },
+            });
+        }
+
+        Ok(RaceSheet { times, distances })
+    }
 
-    let times = input
-        .lines()
-        .collect::<Vec<_>>()
-        .first()
-        .unwrap()
-        .split(' ')
-        .filter(|s| !s.is_empty() && s.parse::<u64>().is_ok())
-        .map(|s| s.parse::<u64>().unwrap())
-        .collect::<Vec<_>>();
-
-    let distances = input
-        .lines()
-        .collect::<Vec<_>>()
-        .get(1)
-        .unwrap()
-        .split(' ')
-        .filter(|s| !s.is_empty() && s.parse::<u64>().is_ok())
-        .map(|s| s.parse::<u64>().unwrap())
-        .collect::<Vec<_>>();
-
-    let races = times
-        .iter()
-        .enumerate()
-        .map(|(index, time)| {
-            let distance = *distances.get(index).unwrap();
+    fn parse_row(
+        row: Option<&'a str>,
+        line_number: usize,
+        label: &'static str,
+    ) -> Result<Vec<Column<'a>>, SheetError> {
+        let line = row.ok_or(SheetError {
+            line: line_number,
+            column: 1,
+            kind: SheetErrorKind::MissingRow(label),
+        })?;
+
+        let numbers = line.strip_prefix(label).ok_or(SheetError {
+            line: line_number,
+            column: 1,
+            kind: SheetErrorKind::MissingLabel(label),
+        })?;
+
+        let columns = numbers
+            .split(' ')
+            .scan(label.len(), |offset, value| {
+                let column = *offset + 1;
+                *offset += value.len() + 1;
+                Some((column, value))
+            })
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(column, value)| {
+                if value.chars().all(|c| c.is_ascii_digit()) {
+                    Ok(Column {
+                        value,
+                        line: line_number,
+                        column,
+                    })
+                } else {
+                    Err(SheetError {
+                        line: line_number,
+                        column,
+                        kind: SheetErrorKind::InvalidNumber(value.to_string()),
+                    })
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-            Race::from((distance, *time))
-        })
-        .collect();
+        if columns.is_empty() {
+            return Err(SheetError {
+                line: line_number,
+                column: 1,
+                kind: SheetErrorKind::MissingNumbers(label),
+            });
+        }
 
-    let result = solve_part1(races);
+        Ok(columns)
+    }
 
-    println!("Result: {}", result);
+    fn races<T: RaceInt>(&self) -> Result<Vec<Race<T>>, SheetError> {
+        self.times
+            .iter()
+            .zip(self.distances.iter())
+            .map(|(time, distance)| Ok(Race::from((distance.parse()?, time.parse()?))))
+            .collect()
+    }
 
-    let input = include_str!("assets/day6/input");
+    // ignores the spaces between the columns and reads each row as a single number
+    fn kerned_race<T: RaceInt>(&self) -> Result<Race<T>, SheetError> {
+        let kern = |columns: &[Column<'a>], line: usize| {
+            let value = columns
+                .iter()
+                .map(|column| column.value)
+                .collect::<String>();
+            let column = columns.first().map(|column| column.column).unwrap_or(1);
+
+            value.parse::<T>().map_err(|_| SheetError {
+                line,
+                column,
+                kind: SheetErrorKind::Overflow(value),
+            })
+        };
 
-    let nums: Vec<_> = input
-        .lines()
-        .map(|line| {
-            line.split(' ')
-                .filter(|s| !s.is_empty() && s.parse::<u32>().is_ok())
-                .collect::<Vec<_>>()
-                .join("")
-                .parse::<u64>()
-                .unwrap()
-        })
-        .collect();
+        Ok(Race::from((
+            kern(&self.distances, 2)?,
+            kern(&self.times, 1)?,
+        )))
+    }
+}
 
-    let game = Race::from((*nums.get(1).unwrap(), *nums.first().unwrap()));
+fn main() {
+    let input = include_str!("assets/day6/input");
 
-    let result = solve_part2(game);
+    // custom physics for part 1, e.g. --acceleration 2 --max-speed 30 --min-charge 3
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let flag = |name: &str| {
+        args.windows(2)
+            .find(|pair| pair[0] == name)
+            .map(|pair| pair[1].parse::<u64>().expect("invalid physics value"))
+    };
+    let physics = Physics {
+        acceleration: flag("--acceleration").unwrap_or(1),
+        max_speed: flag("--max-speed"),
+        min_charge: flag("--min-charge").unwrap_or(0),
+    };
+
+    let sheet = match RaceSheet::parse(input) {
+        Ok(sheet) => sheet,
+        Err(err) => return eprintln!("{}", err),
+    };
+
+    match sheet.races::<u64>() {
+        Ok(races) => {
+            let races = races
+                .into_iter()
+                .map(|race| race.with_physics(physics.clone()))
+                .collect();
+            println!("Result: {}", solve_part1(races))
+        }
+        Err(err) => eprintln!("{}", err),
+    }
 
-    println!("Result: {}", result);
+    match sheet.kerned_race::<KernedInt>() {
+        Ok(race) => println!("Result: {}", solve_part2(race)),
+        Err(err) => eprintln!("{}", err),
+    }
 }
 
 #[cfg(test)]
@@ -114,36 +468,7 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = include_str!("assets/day6/input_test");
-
-        let times = input
-            .lines()
-            .collect::<Vec<_>>()
-            .first()
-            .unwrap()
-            .split(' ')
-            .filter(|s| !s.is_empty() && s.parse::<u64>().is_ok())
-            .map(|s| s.parse::<u64>().unwrap())
-            .collect::<Vec<_>>();
-
-        let distances = input
-            .lines()
-            .collect::<Vec<_>>()
-            .get(1)
-            .unwrap()
-            .split(' ')
-            .filter(|s| !s.is_empty() && s.parse::<u64>().is_ok())
-            .map(|s| s.parse::<u64>().unwrap())
-            .collect::<Vec<_>>();
-
-        let races = times
-            .iter()
-            .enumerate()
-            .map(|(index, time)| {
-                let distance = *distances.get(index).unwrap();
-
-                Race::from((distance, *time))
-            })
-            .collect();
+        let races = RaceSheet::parse(input).unwrap().races::<u64>().unwrap();
 
         assert_eq!(solve_part1(races), 288);
     }
@@ -151,20 +476,10 @@ mod tests {
     #[test]
     fn test_part2() {
         let input = include_str!("assets/day6/input_test");
-
-        let nums: Vec<_> = input
-            .lines()
-            .map(|line| {
-                line.split(' ')
-                    .filter(|s| !s.is_empty() && s.parse::<u32>().is_ok())
-                    .collect::<Vec<_>>()
-                    .join("")
-                    .parse::<u64>()
-                    .unwrap()
-            })
-            .collect();
-
-        let race = Race::from((*nums.get(1).unwrap(), *nums.first().unwrap()));
+        let race = RaceSheet::parse(input)
+            .unwrap()
+            .kerned_race::<u64>()
+            .unwrap();
 
         assert_eq!(race.duration, 71530);
         assert_eq!(race.distance, 940200);
@@ -173,4 +488,237 @@ mod tests {
 
         assert_eq!(result, 71503);
     }
+
+    #[test]
+    fn test_sheet_keeps_wide_columns() {
+        let input = "Time:      7  15   5000000000\nDistance:  9  40  200";
+        let sheet = RaceSheet::parse(input).unwrap();
+
+        let races = sheet.races::<u64>().unwrap();
+        assert_eq!(races[2].duration, 5_000_000_000);
+
+        let race = sheet.kerned_race::<u128>().unwrap();
+        assert_eq!(race.duration, 7_155_000_000_000);
+        assert_eq!(race.distance, 940200);
+    }
+
+    #[test]
+    fn test_sheet_errors() {
+        let error = |input| RaceSheet::parse(input).err().unwrap();
+
+        assert_eq!(
+            error("Time:      7  15   30\nDistance:  9  40"),
+            SheetError {
+                line: 2,
+                column: 17,
+                kind: SheetErrorKind::ColumnMismatch {
+                    times: 3,
+                    distances: 2
+                },
+            }
+        );
+        assert_eq!(
+            error("Time:      7  15\nDistance:  9  40  200"),
+            SheetError {
+                line: 2,
+                column: 19,
+                kind: SheetErrorKind::ColumnMismatch {
+                    times: 2,
+                    distances: 3
+                },
+            }
+        );
+        assert_eq!(
+            error("Time:      7  1x5   30\nDistance:  9  40  200"),
+            SheetError {
+                line: 1,
+                column: 15,
+                kind: SheetErrorKind::InvalidNumber("1x5".to_string()),
+            }
+        );
+        assert_eq!(
+            error("Time: 7\nDist: 9"),
+            SheetError {
+                line: 2,
+                column: 1,
+                kind: SheetErrorKind::MissingLabel("Distance:"),
+            }
+        );
+        assert_eq!(
+            error("Time:\nDistance:"),
+            SheetError {
+                line: 1,
+                column: 1,
+                kind: SheetErrorKind::MissingNumbers("Time:"),
+            }
+        );
+        assert_eq!(
+            error("Time: 7\nDistance:   "),
+            SheetError {
+                line: 2,
+                column: 1,
+                kind: SheetErrorKind::MissingNumbers("Distance:"),
+            }
+        );
+        assert_eq!(
+            error("Time: 7"),
+            SheetError {
+                line: 2,
+                column: 1,
+                kind: SheetErrorKind::MissingRow("Distance:"),
+            }
+        );
+
+        let sheet = RaceSheet::parse("Time: 99999999999999999999 1\nDistance: 9 2").unwrap();
+        assert_eq!(
+            sheet.races::<u64>().err().unwrap(),
+            SheetError {
+                line: 1,
+                column: 7,
+                kind: SheetErrorKind::Overflow("99999999999999999999".to_string()),
+            }
+        );
+        assert_eq!(
+            sheet.kerned_race::<u128>().unwrap().duration,
+            999_999_999_999_999_999_991
+        );
+    }
+
+    #[test]
+    fn test_winning_holds() {
+        assert_eq!(Race::from((9u64, 7)).winning_holds(), Some(2..=5));
+        // holding 10 or 20 exactly ties the record
+        assert_eq!(Race::from((200u64, 30)).winning_holds(), Some(11..=19));
+        // the best hold only ties the record
+        assert_eq!(Race::from((4u64, 4)).winning_holds(), None);
+        assert_eq!(Race::from((3u64, 4)).winning_holds(), Some(2..=2));
+        assert_eq!(Race::from((100u64, 10)).winning_holds(), None);
+        assert_eq!(Race::from((0u64, 0)).winning_holds(), None);
+        assert_eq!(Race::from((0u64, 1)).winning_holds(), None);
+    }
+
+    #[test]
+    fn test_winning_holds_large_durations() {
+        let duration = 8_000_000_000u64;
+        let hold = 1_234_567_891u64;
+        let distance = hold * (duration - hold);
+
+        let race = Race::from((distance, duration));
+        assert_eq!(race.winning_holds(), Some(hold + 1..=duration - hold - 1));
+
+        let race = Race::from((distance - 1, duration));
+        assert_eq!(race.winning_holds(), Some(hold..=duration - hold));
+    }
+
+    #[test]
+    fn test_wide_integers() {
+        // the square of the duration overflows u64, bisection finds the same interval
+        let race = Race::from((9_000_000_000_000_000_000u64, 10_000_000_000u64));
+        let wide = Race::from((9_000_000_000_000_000_000u128, 10_000_000_000u128));
+
+        assert_eq!(race.winning_holds(), Some(1_000_000_001..=8_999_999_999));
+        assert_eq!(wide.winning_holds(), Some(1_000_000_001..=8_999_999_999));
+
+        let race = Race::from((u64::MAX, u64::MAX));
+        let wide = Race::from((u64::MAX as u128, u64::MAX as u128));
+        let holds = race.winning_holds().unwrap();
+        let wide_holds = wide.winning_holds().unwrap();
+
+        assert_eq!(*holds.start() as u128, *wide_holds.start());
+        assert_eq!(*holds.end() as u128, *wide_holds.end());
+        assert_eq!(holds, 2..=u64::MAX - 2);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_big_integers() {
+        use num_bigint::BigUint;
+
+        let duration = "7".repeat(60).parse::<BigUint>().unwrap();
+        let hold = "1".repeat(30).parse::<BigUint>().unwrap();
+        let distance = hold.clone() * (duration.clone() - hold.clone());
+
+        let race = Race::from((distance, duration.clone()));
+        let holds = race.winning_holds().unwrap();
+
+        assert_eq!(*holds.start(), hold.clone() + BigUint::from(1u64));
+        assert_eq!(
+            *holds.end(),
+            duration.clone() - hold.clone() - BigUint::from(1u64)
+        );
+        assert_eq!(
+            race.ways_to_win(),
+            duration - hold.clone() - hold - BigUint::from(1u64)
+        );
+    }
+
+    // counts winning holds one by one as reference for the general solver
+    fn brute_force(race: &Race<u64>) -> Vec<u64> {
+        (0..=race.duration).filter(|hold| race.wins(hold)).collect()
+    }
+
+    #[test]
+    fn test_general_physics() {
+        let physics = Physics {
+            acceleration: 2,
+            max_speed: Some(9),
+            min_charge: 3,
+        };
+        let race = Race::from((40u64, 15)).with_physics(physics.clone());
+
+        // speed is 0 before 3 ms, then 6 and 8 and capped at 9 afterwards
+        assert_eq!(race.travelled(&2), Some(0));
+        assert_eq!(race.travelled(&3), Some(6 * 12));
+        assert_eq!(race.travelled(&10), Some(9 * 5));
+        assert_eq!(race.winning_holds(), Some(3..=10));
+
+        for distance in 0..150 {
+            for duration in 0..40 {
+                let race = Race::from((distance, duration)).with_physics(physics.clone());
+                let expected = brute_force(&race);
+
+                let holds = race.winning_holds();
+                assert_eq!(
+                    holds
+                        .map(|holds| holds.collect::<Vec<_>>())
+                        .unwrap_or_default(),
+                    expected,
+                    "distance {} duration {}",
+                    distance,
+                    duration
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_general_physics_matches_standard() {
+        let physics = Physics {
+            acceleration: 1,
+            max_speed: None,
+            min_charge: 1,
+        };
+
+        for (distance, duration) in [(9u64, 7u64), (40, 15), (200, 30), (4, 4), (0, 1)] {
+            let standard = Race::from((distance, duration));
+            let general = Race::from((distance, duration)).with_physics(physics.clone());
+
+            assert_eq!(standard.winning_holds(), general.general_winning_holds());
+            assert_eq!(standard.winning_holds(), general.winning_holds());
+        }
+    }
+
+    #[test]
+    fn test_general_physics_large_values() {
+        let physics = Physics {
+            acceleration: 1_000,
+            max_speed: Some(u64::MAX / 2),
+            min_charge: 5,
+        };
+        let race = Race::from((u64::MAX - 1, 1_000_000_000_000u64)).with_physics(physics);
+
+        let holds = race.winning_holds().unwrap();
+        assert!(race.wins(holds.start()) && !race.wins(&(holds.start() - 1)));
+        assert!(race.wins(holds.end()) && !race.wins(&(holds.end() + 1)));
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Clone)]
 enum Direction {
@@ -6,170 +6,631 @@ enum Direction {
     Right,
 }
 
-impl From<char> for Direction {
-    fn from(c: char) -> Direction {
+impl TryFrom<char> for Direction {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Direction, char> {
         match c {
-            'L' => Direction::Left,
-            'R' => Direction::Right,
-            _ => panic!("invalid direction"),
+            'L' => Ok(Direction::Left),
+            'R' => Ok(Direction::Right),
+            _ => Err(c),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    MissingNodes,
+    EmptyDirections,
+    InvalidDirection { position: usize, value: char },
+    InvalidNode { line: usize },
+    DuplicateNode { line: usize, name: String },
+    UnknownNode { line: usize, name: String },
+    MissingNode { name: String },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::MissingNodes => {
+                write!(f, "expected a blank line between directions and nodes")
+            }
+            ParseError::EmptyDirections => write!(f, "line 1: no directions given"),
+            ParseError::InvalidDirection { position, value } => {
+                write!(
+                    f,
+                    "line 1, column {}: invalid direction {:?}",
+                    position, value
+                )
+            }
+            ParseError::InvalidNode { line } => {
+                write!(f, "line {}: expected 'AAA = (BBB, CCC)'", line)
+            }
+            ParseError::DuplicateNode { line, name } => {
+                write!(f, "line {}: duplicate node {}", line, name)
+            }
+            ParseError::UnknownNode { line, name } => {
+                write!(f, "line {}: unknown node {}", line, name)
+            }
+            ParseError::MissingNode { name } => write!(f, "network has no node {}", name),
         }
     }
 }
+
+// nodes interned into integer ids, each step is a lookup in `left` or `right`
+struct Network {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+    // ghosts start on nodes ending in A and stop on nodes ending in Z
+    starts: Vec<usize>,
+    is_end: Vec<bool>,
+}
+
+impl Network {
+    fn parse(input: &str) -> Result<(Vec<Direction>, Network), ParseError> {
+        let (directions, nodes) = input.split_once("\n\n").ok_or(ParseError::MissingNodes)?;
+
+        let directions = directions
+            .trim_end()
+            .chars()
+            .enumerate()
+            .map(|(index, c)| {
+                Direction::try_from(c).map_err(|value| ParseError::InvalidDirection {
+                    position: index + 1,
+                    value,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if directions.is_empty() {
+            return Err(ParseError::EmptyDirections);
+        }
+
+        // node lines start after the directions and the blank line
+        let mut locations = BTreeMap::new();
+        for (index, line) in nodes.lines().enumerate() {
+            let line_number = index + 3;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (name, options) =
+                parse_node(line).ok_or(ParseError::InvalidNode { line: line_number })?;
+
+            if locations.insert(name, (line_number, options)).is_some() {
+                return Err(ParseError::DuplicateNode {
+                    line: line_number,
+                    name: name.to_string(),
+                });
+            }
+        }
+
+        let names = locations
+            .keys()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id))
+            .collect::<HashMap<_, _>>();
+
+        let lookup = |line: usize, name: &str| -> Result<usize, ParseError> {
+            ids.get(name).copied().ok_or(ParseError::UnknownNode {
+                line,
+                name: name.to_string(),
+            })
+        };
+
+        let left = locations
+            .values()
+            .map(|(line, (left, _))| lookup(*line, left))
+            .collect::<Result<Vec<_>, _>>()?;
+        let right = locations
+            .values()
+            .map(|(line, (_, right))| lookup(*line, right))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let starts = (0..names.len())
+            .filter(|id| names[*id].ends_with('A'))
+            .collect();
+        let is_end = names.iter().map(|name| name.ends_with('Z')).collect();
+
+        let network = Network {
+            names,
+            ids,
+            left,
+            right,
+            starts,
+            is_end,
+        };
+
+        Ok((directions, network))
+    }
+
+    fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    fn next(&self, node: usize, direction: &Direction) -> usize {
+        match direction {
+            Direction::Left => self.left[node],
+            Direction::Right => self.right[node],
+        }
+    }
+
+    // nodes visited when following the directions for the given number of steps
+    fn walk(&self, directions: &[Direction], start: usize, steps: usize) -> Vec<usize> {
+        directions
+            .iter()
+            .cycle()
+            .take(steps)
+            .scan(start, |node, direction| {
+                *node = self.next(*node, direction);
+                Some(*node)
+            })
+            .fold(vec![start], |mut path, node| {
+                path.push(node);
+                path
+            })
+    }
+
+    // Graphviz DOT with the walk's start node filled green and its end node filled
+    // red, the ghost start and end nodes outlined in the same colours and the edges
+    // of the optional path in blue
+    fn to_dot(&self, start: Option<usize>, end: Option<usize>, path: Option<&[usize]>) -> String {
+        let path_edges = path
+            .map(|path| {
+                path.windows(2)
+                    .map(|edge| (edge[0], edge[1]))
+                    .collect::<HashSet<_>>()
+            })
+            .unwrap_or_default();
+
+        let mut dot = String::from("digraph network {\n");
+
+        for (node, name) in self.names.iter().enumerate() {
+            let mut attributes = Vec::new();
+
+            if self.starts.contains(&node) {
+                attributes.push("color=darkgreen");
+            } else if self.is_end[node] {
+                attributes.push("color=firebrick");
+            }
+
+            if start == Some(node) {
+                attributes.push("style=filled, fillcolor=palegreen, shape=doublecircle");
+            } else if end == Some(node) {
+                attributes.push("style=filled, fillcolor=salmon, shape=doublecircle");
+            }
+
+            if !attributes.is_empty() {
+                dot.push_str(&format!("    {:?} [{}];\n", name, attributes.join(", ")));
+            }
+        }
+
+        for (node, name) in self.names.iter().enumerate() {
+            let (left, right) = (self.left[node], self.right[node]);
+            let edges = if left == right {
+                vec![(left, "LR")]
+            } else {
+                vec![(left, "L"), (right, "R")]
+            };
+
+            for (target, label) in edges {
+                let style = if path_edges.contains(&(node, target)) {
+                    ", color=blue, penwidth=2"
+                } else {
+                    ""
+                };
+
+                dot.push_str(&format!(
+                    "    {:?} -> {:?} [label={:?}{}];\n",
+                    name, self.names[target], label, style
+                ));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// splits `AAA = (BBB, CCC)` into the node and its left and right options
+fn parse_node(line: &str) -> Option<(&str, (&str, &str))> {
+    let (name, options) = line.split_once('=')?;
+    let (left, right) = options
+        .trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .split_once(',')?;
+
+    let (name, left, right) = (name.trim(), left.trim(), right.trim());
+    if [name, left, right].iter().any(|part| part.is_empty()) {
+        return None;
+    }
+
+    Some((name, (left, right)))
+}
+
 fn main() {
     let input = include_str!("assets/day8/input");
 
-    let result = solve_part1(input);
-    println!("Part 1: {}", result);
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let flag = |name: &str| {
+        args.windows(2)
+            .find(|pair| pair[0] == name)
+            .map(|pair| pair[1].as_str())
+    };
 
-    let result = solve_part2(input);
-    println!("Part 2: {}", result);
+    // part 1, the DOT path and --after go from --start to --end, AAA and ZZZ by default
+    let start = flag("--start").unwrap_or("AAA");
+    let end = flag("--end").unwrap_or("ZZZ");
+
+    // print the network with the path from start to end as Graphviz DOT instead
+    if args.iter().any(|arg| arg == "--dot") {
+        match network_dot(input, start, end) {
+            Ok(dot) => print!("{}", dot),
+            Err(err) => eprintln!("{}", err),
+        }
+        return;
+    }
+
+    // print the node reached from start after the given number of steps and the
+    // first step on a Z node instead
+    if let Some(steps) =
+        flag("--after").map(|steps| steps.parse::<u64>().expect("invalid number of steps"))
+    {
+        match node_after(input, start, steps) {
+            Ok((node, first_z)) => {
+                println!("After {} steps: {}", steps, node);
+
+                if let Some(first_z) = first_z {
+                    println!("First Z node after {} steps", first_z);
+                }
+            }
+            Err(err) => eprintln!("{}", err),
+        }
+        return;
+    }
+
+    match solve_part1(input, start, end) {
+        Ok(Some(result)) => println!("Part 1: {}", result),
+        Ok(None) => println!("Part 1: {} is never reached from {}", end, start),
+        Err(err) => return eprintln!("{}", err),
+    }
+
+    match solve_part2(input) {
+        Ok(Some(result)) => println!("Part 2: {}", result),
+        Ok(None) => println!("Part 2: ghosts never meet on Z nodes"),
+        Err(err) => eprintln!("{}", err),
+    }
 }
 
-fn solve_part1(input: &str) -> usize {
-    let input = input.split("\n\n").collect::<Vec<_>>();
+fn solve_part1(input: &str, start: &str, end: &str) -> Result<Option<usize>, ParseError> {
+    let (directions, network) = Network::parse(input)?;
 
-    let directions = input
-        .first()
-        .unwrap()
-        .chars()
-        .map(Direction::from)
-        .collect::<Vec<_>>();
+    steps_between(&network, &directions, start, end)
+}
 
-    let locations = input
-        .get(1)
-        .unwrap()
-        .split('\n')
-        .filter(|s| !s.is_empty())
-        .map(|s| {
-            let parts = s.split('=').collect::<Vec<_>>();
-            let location = parts.first().unwrap().trim().to_string();
-
-            let opt_parts = parts
-                .get(1)
-                .unwrap()
-                .split(',')
-                .map(|s| s.trim())
-                .collect::<Vec<_>>();
-            let opt_parts = opt_parts
-                .iter()
-                .map(|s| s.trim_start_matches('(').trim_end_matches(')'))
-                .collect::<Vec<_>>();
-
-            assert_eq!(opt_parts.len(), 2);
-
-            let options = (
-                opt_parts.first().unwrap().to_string(),
-                opt_parts.last().unwrap().to_string(),
-            );
-
-            (location, options)
-        })
-        .collect::<BTreeMap<_, _>>();
+fn network_dot(input: &str, start: &str, end: &str) -> Result<String, ParseError> {
+    let (directions, network) = Network::parse(input)?;
 
-    let mut location = locations.keys().next().unwrap().to_owned();
-    let mut count_steps = 0u64;
+    // without both nodes or a way from start to end the network is rendered alone
+    let path = match steps_between(&network, &directions, start, end) {
+        Ok(Some(steps)) => network
+            .id(start)
+            .map(|start| network.walk(&directions, start, steps)),
+        _ => None,
+    };
 
-    loop {
-        for direction in directions.clone() {
-            location = match direction {
-                Direction::Left => {
-                    let location = &locations.get(&location).unwrap().0;
-                    count_steps += 1;
+    Ok(network.to_dot(network.id(start), network.id(end), path.as_deref()))
+}
 
-                    location.to_owned()
-                }
-                Direction::Right => {
-                    let location = &locations.get(&location).unwrap().1;
-                    count_steps += 1;
+fn node_after(input: &str, start: &str, steps: u64) -> Result<(String, Option<u64>), ParseError> {
+    let (directions, network) = Network::parse(input)?;
+    let start = network.id(start).ok_or(ParseError::MissingNode {
+        name: start.to_string(),
+    })?;
+    let jump_table = JumpTable::new(&network, &directions);
 
-                    location.to_owned()
-                }
+    let first_z = jump_table.first_z(start);
+
+    Ok((
+        network.names[jump_table.after_steps(start, steps)].clone(),
+        first_z,
+    ))
+}
+
+// number of steps from start until end is reached, checked after every single step,
+// None once every (node, instruction index) state could have been visited
+fn steps_between(
+    network: &Network,
+    directions: &[Direction],
+    start: &str,
+    end: &str,
+) -> Result<Option<usize>, ParseError> {
+    let start = network.id(start).ok_or(ParseError::MissingNode {
+        name: start.to_string(),
+    })?;
+    let end = network.id(end).ok_or(ParseError::MissingNode {
+        name: end.to_string(),
+    })?;
+
+    let max_steps = network.names.len() * directions.len();
+    let mut node = start;
+
+    for (count_steps, direction) in directions.iter().cycle().take(max_steps).enumerate() {
+        if node == end {
+            return Ok(Some(count_steps));
+        }
+
+        node = network.next(node, direction);
+    }
+
+    Ok((node == end).then_some(max_steps))
+}
+
+// part 2 fails on invalid input or if the ghosts meet after more steps than fit into u64
+#[derive(Debug, PartialEq)]
+enum SolveError {
+    Parse(ParseError),
+    StepOverflow,
+}
+
+impl From<ParseError> for SolveError {
+    fn from(err: ParseError) -> SolveError {
+        SolveError::Parse(err)
+    }
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Parse(err) => write!(f, "{}", err),
+            SolveError::StepOverflow => {
+                write!(f, "ghosts only meet after more than {} steps", u64::MAX)
             }
         }
+    }
+}
+
+// node reached after whole passes of the instructions, doubled so that
+// 2^k passes are a single lookup
+struct JumpTable<'a> {
+    network: &'a Network,
+    directions: &'a [Direction],
+    // jumps[k][node] is the node reached after 2^k passes starting at node
+    jumps: Vec<Vec<usize>>,
+    // steps within one pass starting at node at which a Z node is occupied
+    z_hits: Vec<Vec<usize>>,
+}
+
+impl<'a> JumpTable<'a> {
+    fn new(network: &'a Network, directions: &'a [Direction]) -> JumpTable<'a> {
+        let (pass, z_hits) = (0..network.names.len())
+            .map(|start| {
+                let mut node = start;
+                let mut z_hits = Vec::new();
+
+                for (step, direction) in directions.iter().enumerate() {
+                    if network.is_end[node] {
+                        z_hits.push(step);
+                    }
+                    node = network.next(node, direction);
+                }
+
+                (node, z_hits)
+            })
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+
+        // enough levels to cover any step count that fits into u64
+        let max_passes = u64::MAX / directions.len() as u64;
+        let levels = (u64::BITS - max_passes.leading_zeros()).max(1);
+
+        let mut jumps = vec![pass];
+        for level in 1..levels as usize {
+            let previous = &jumps[level - 1];
+            let doubled = previous.iter().map(|node| previous[*node]).collect();
+            jumps.push(doubled);
+        }
 
-        if location == *locations.keys().last().unwrap() {
-            break;
+        JumpTable {
+            network,
+            directions,
+            jumps,
+            z_hits,
         }
     }
 
-    count_steps as usize
+    fn after_passes(&self, start: usize, passes: u64) -> usize {
+        self.jumps
+            .iter()
+            .enumerate()
+            .filter(|(level, _)| passes >> level & 1 == 1)
+            .fold(start, |node, (_, jump)| jump[node])
+    }
+
+    // node reached after the given number of single steps
+    fn after_steps(&self, start: usize, steps: u64) -> usize {
+        let period = self.directions.len() as u64;
+        let node = self.after_passes(start, steps / period);
+
+        self.directions[..(steps % period) as usize]
+            .iter()
+            .fold(node, |node, direction| self.network.next(node, direction))
+    }
+
+    // first step at which a Z node is occupied, skipping passes without Z hits
+    fn first_z(&self, start: usize) -> Option<u64> {
+        let period = self.directions.len() as u64;
+        let mut node = start;
+
+        // the pass start nodes repeat after at most one pass per node
+        for pass in 0..=self.network.names.len() as u64 {
+            if let Some(step) = self.z_hits[node].first() {
+                return Some(pass * period + *step as u64);
+            }
+            node = self.jumps[0][node];
+        }
+
+        None
+    }
 }
 
-fn solve_part2(input: &str) -> usize {
-    let input = input.split("\n\n").collect::<Vec<_>>();
+// None if there are no ghosts or they never are on Z nodes at the same time
+fn solve_part2(input: &str) -> Result<Option<usize>, SolveError> {
+    let (directions, network) = Network::parse(input)?;
 
-    let directions = input
-        .first()
-        .unwrap()
-        .chars()
-        .map(Direction::from)
+    let cycles = network
+        .starts
+        .iter()
+        .map(|start| detect_cycle(&network, &directions, *start))
         .collect::<Vec<_>>();
 
-    let locations = input
-        .get(1)
-        .unwrap()
-        .split('\n')
-        .filter(|s| !s.is_empty())
-        .map(|s| {
-            let parts = s.split('=').collect::<Vec<_>>();
-            let location = parts.first().unwrap().trim().to_string();
-
-            let opt_parts = parts
-                .get(1)
-                .unwrap()
-                .split(',')
-                .map(|s| s.trim())
-                .collect::<Vec<_>>();
-            let opt_parts = opt_parts
-                .iter()
-                .map(|s| s.trim_start_matches('(').trim_end_matches(')'))
-                .collect::<Vec<_>>();
-
-            assert_eq!(opt_parts.len(), 2);
-
-            let options = (
-                opt_parts.first().unwrap().to_string(),
-                opt_parts.last().unwrap().to_string(),
-            );
-
-            (location, options)
-        })
-        .collect::<BTreeMap<_, _>>();
+    first_common_z(&cycles)?
+        .map(|step| usize::try_from(step).map_err(|_| SolveError::StepOverflow))
+        .transpose()
+}
 
-    let start_locations = locations
-        .keys()
-        .filter(|loc| loc.ends_with('A'))
-        .map(|loc| loc.to_owned())
-        .collect::<Vec<_>>();
+// every walk ends up looping over the same (node, instruction index) states
+#[derive(Debug, PartialEq)]
+struct GhostCycle {
+    // step at which the loop starts
+    offset: usize,
+    length: usize,
+    // steps before offset + length at which the ghost is on a Z node
+    z_positions: Vec<usize>,
+}
+
+impl GhostCycle {
+    fn is_z_at(&self, step: usize) -> bool {
+        let step = if step < self.offset {
+            step
+        } else {
+            self.offset + (step - self.offset) % self.length
+        };
+
+        self.z_positions.contains(&step)
+    }
+}
 
-    let mut iterations = Vec::new();
+fn detect_cycle(network: &Network, directions: &[Direction], start: usize) -> GhostCycle {
+    // first step at which each (node, instruction index) state was seen
+    let mut seen = vec![None; network.names.len() * directions.len()];
+    let mut z_positions = Vec::new();
+    let mut node = start;
+    let mut step = 0;
 
-    for start_location in start_locations {
-        let mut location = start_location;
-        let mut step_count = 0u64;
-        let mut directions_iter = directions.iter().cycle();
+    loop {
+        let instruction = step % directions.len();
+        let state = node * directions.len() + instruction;
 
-        while !location.ends_with('Z') {
-            if let Some(direction) = directions_iter.next() {
-                location = match direction {
-                    Direction::Left => locations[&location].0.clone(),
-                    Direction::Right => locations[&location].1.clone(),
-                };
-                step_count += 1;
-            }
+        if let Some(offset) = seen[state] {
+            return GhostCycle {
+                offset,
+                length: step - offset,
+                z_positions,
+            };
+        }
+        seen[state] = Some(step);
+
+        if network.is_end[node] {
+            z_positions.push(step);
         }
 
-        iterations.push(step_count);
+        node = network.next(node, &directions[instruction]);
+        step += 1;
+    }
+}
+
+// returns (gcd, x, y) with a * x + b * y = gcd
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (gcd, x, y) = extended_gcd(b, a % b);
+        (gcd, y, x - a / b * y)
+    }
+}
+
+// chinese remainder theorem for moduli that are not necessarily coprime
+fn combine_congruences(
+    (a1, m1): (i128, i128),
+    (a2, m2): (i128, i128),
+) -> Result<Option<(i128, i128)>, SolveError> {
+    let (gcd, inverse, _) = extended_gcd(m1, m2);
+
+    if (a2 - a1) % gcd != 0 {
+        return Ok(None);
     }
 
-    let mut least_common_multiple = iterations.pop().unwrap();
+    let lcm = (m1 / gcd).checked_mul(m2).ok_or(SolveError::StepOverflow)?;
+    let k = ((a2 - a1) / gcd)
+        .checked_mul(inverse)
+        .ok_or(SolveError::StepOverflow)?
+        .rem_euclid(m2 / gcd);
+    let remainder = m1
+        .checked_mul(k)
+        .and_then(|step| step.checked_add(a1))
+        .ok_or(SolveError::StepOverflow)?;
+
+    Ok(Some((remainder.rem_euclid(lcm), lcm)))
+}
+
+// first step at which all ghosts are on a Z node at the same time
+fn first_common_z(cycles: &[GhostCycle]) -> Result<Option<u64>, SolveError> {
+    let Some(max_offset) = cycles.iter().map(|cycle| cycle.offset).max() else {
+        return Ok(None);
+    };
 
-    for iteration in iterations {
-        least_common_multiple =
-            least_common_multiple * iteration / gcd::binary_u64(least_common_multiple, iteration)
+    // before every ghost is inside its loop the steps are checked one by one
+    if let Some(step) = (0..max_offset).find(|step| cycles.iter().all(|cycle| cycle.is_z_at(*step)))
+    {
+        return Ok(Some(step as u64));
     }
 
-    least_common_multiple as usize
+    // afterwards each ghost needs step = z (mod length) for one of its looped Z positions
+    let solutions = cycles.iter().try_fold(
+        vec![(0i128, 1i128)],
+        |solutions, cycle| -> Result<_, SolveError> {
+            let mut combined = Vec::new();
+
+            for solution in solutions.iter() {
+                for z in cycle.z_positions.iter().filter(|z| **z >= cycle.offset) {
+                    let congruence = (*z as i128, cycle.length as i128);
+                    combined.extend(combine_congruences(*solution, congruence)?);
+                }
+            }
+
+            Ok(combined)
+        },
+    )?;
+
+    // the smallest solution that is not before every ghost is in its loop
+    let min = max_offset as i128;
+    let steps = solutions
+        .iter()
+        .map(|(remainder, modulus)| {
+            if *remainder >= min {
+                Some(*remainder)
+            } else {
+                let loops = (min - remainder + modulus - 1) / modulus;
+                loops
+                    .checked_mul(*modulus)
+                    .and_then(|step| step.checked_add(*remainder))
+            }
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or(SolveError::StepOverflow)?;
+
+    steps
+        .into_iter()
+        .min()
+        .map(|step| u64::try_from(step).map_err(|_| SolveError::StepOverflow))
+        .transpose()
 }
 
 #[cfg(test)]
@@ -180,21 +641,402 @@ mod tests {
     #[test]
     fn part1_test1_works() {
         let input = include_str!("assets/day8/input_test1");
-        let result = solve_part1(input);
-        assert_eq!(result, 2);
+        let result = solve_part1(input, "AAA", "ZZZ").unwrap();
+        assert_eq!(result, Some(2));
     }
 
     #[test]
     fn part1_test2_works() {
         let input = include_str!("assets/day8/input_test2");
-        let result = solve_part1(input);
-        assert_eq!(result, 6);
+        let result = solve_part1(input, "AAA", "ZZZ").unwrap();
+        assert_eq!(result, Some(6));
     }
 
     #[test]
     fn part2_test1_works() {
         let input = include_str!("assets/day8/input_test3");
-        let result = solve_part2(input);
-        assert_eq!(result, 6);
+        let result = solve_part2(input).unwrap();
+        assert_eq!(result, Some(6));
+    }
+
+    #[test]
+    fn part1_starts_at_aaa_and_stops_at_zzz() {
+        // 11B sorts before AAA and ZZZ is reached in the middle of the instructions
+        let input = "RL\n\n11B = (11B, 11B)\nAAA = (BBB, ZZZ)\nBBB = (BBB, BBB)\nZZZ = (ZZZ, ZZZ)\nZZZZ = (AAA, AAA)\n";
+        let result = solve_part1(input, "AAA", "ZZZ").unwrap();
+        assert_eq!(result, Some(1));
+    }
+
+    #[test]
+    fn steps_between_stops_on_unreachable_end() {
+        let input = include_str!("assets/day8/input_test1");
+        let (directions, network) = Network::parse(input).unwrap();
+
+        // DDD only leads back to itself
+        assert_eq!(steps_between(&network, &directions, "DDD", "ZZZ"), Ok(None));
+        assert_eq!(steps_between(&network, &directions, "ZZZ", "AAA"), Ok(None));
+
+        let result = solve_part1(input, "AAA", "CCC").unwrap();
+        assert_eq!(result, Some(1));
+        let result = solve_part1(input, "AAA", "EEE").unwrap();
+        assert_eq!(result, None);
+        let result = solve_part1(input, "GGG", "ZZZ").unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn steps_between_custom_nodes() {
+        let input = include_str!("assets/day8/input_test2");
+        let (directions, network) = Network::parse(input).unwrap();
+
+        assert_eq!(
+            steps_between(&network, &directions, "AAA", "ZZZ"),
+            Ok(Some(6))
+        );
+        assert_eq!(
+            steps_between(&network, &directions, "AAA", "BBB"),
+            Ok(Some(1))
+        );
+        assert_eq!(
+            steps_between(&network, &directions, "BBB", "BBB"),
+            Ok(Some(0))
+        );
+        assert_eq!(
+            steps_between(&network, &directions, "BBB", "ZZZ"),
+            Ok(Some(3))
+        );
+    }
+
+    const OFFSET_INPUT: &str = "L\n\n11A = (11B, 11B)\n11B = (11C, 11C)\n11C = (11Z, 11Z)\n11Z = (11C, 11C)\n22A = (22Z, 22Z)\n22Z = (22B, 22B)\n22B = (22C, 22C)\n22C = (22Z, 22Z)";
+
+    #[test]
+    fn detects_ghost_cycles() {
+        let (directions, network) = Network::parse(OFFSET_INPUT).unwrap();
+
+        assert_eq!(
+            detect_cycle(&network, &directions, network.id("11A").unwrap()),
+            GhostCycle {
+                offset: 2,
+                length: 2,
+                z_positions: vec![3],
+            }
+        );
+        assert_eq!(
+            detect_cycle(&network, &directions, network.id("22A").unwrap()),
+            GhostCycle {
+                offset: 1,
+                length: 3,
+                z_positions: vec![1],
+            }
+        );
+    }
+
+    #[test]
+    fn part2_handles_cycle_offsets() {
+        // first Z hits are 3 and 1, but the ghosts only meet at step 7
+        assert_eq!(solve_part2(OFFSET_INPUT), Ok(Some(7)));
+    }
+
+    #[test]
+    fn part2_without_common_z() {
+        assert_eq!(solve_part2("L\n\nBBB = (BBB, BBB)\n"), Ok(None));
+
+        // the first ghost is on Z at odd steps, the second one at even steps
+        let input = "L\n\n11A = (11Z, 11Z)\n11Z = (11A, 11A)\n22A = (22B, 22B)\n22B = (22Z, 22Z)\n22Z = (22B, 22B)";
+        assert_eq!(solve_part2(input), Ok(None));
+    }
+
+    #[test]
+    fn first_common_z_before_cycles() {
+        let cycles = [
+            GhostCycle {
+                offset: 5,
+                length: 4,
+                z_positions: vec![2],
+            },
+            GhostCycle {
+                offset: 0,
+                length: 2,
+                z_positions: vec![0],
+            },
+        ];
+        assert_eq!(first_common_z(&cycles), Ok(Some(2)));
+
+        let cycles = [
+            GhostCycle {
+                offset: 0,
+                length: 4,
+                z_positions: vec![1],
+            },
+            GhostCycle {
+                offset: 0,
+                length: 2,
+                z_positions: vec![0],
+            },
+        ];
+        assert_eq!(first_common_z(&cycles), Ok(None));
+    }
+
+    #[test]
+    fn combines_congruences_with_common_factors() {
+        assert_eq!(combine_congruences((3, 6), (1, 4)), Ok(Some((9, 12))));
+        assert_eq!(combine_congruences((0, 6), (1, 4)), Ok(None));
+        assert_eq!(combine_congruences((2, 3), (3, 5)), Ok(Some((8, 15))));
+        assert_eq!(
+            combine_congruences((0, 1 << 126), (1, 3)),
+            Err(SolveError::StepOverflow)
+        );
+    }
+
+    #[test]
+    fn interns_node_names() {
+        let input = include_str!("assets/day8/input_test3");
+        let (_, network) = Network::parse(input).unwrap();
+
+        assert_eq!(network.names.len(), 8);
+        assert_eq!(network.id("11A"), Some(0));
+        assert_eq!(network.id("XXX"), Some(7));
+        assert_eq!(network.id("ZZZ"), None);
+
+        let node = network.id("11B").unwrap();
+        assert_eq!(network.names[network.next(node, &Direction::Left)], "XXX");
+        assert_eq!(network.names[network.next(node, &Direction::Right)], "11Z");
+
+        let starts = network
+            .starts
+            .iter()
+            .map(|start| network.names[*start].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec!["11A", "22A"]);
+        assert_eq!(network.is_end.iter().filter(|end| **end).count(), 2);
+    }
+
+    #[test]
+    fn renders_network_as_dot() {
+        let input = include_str!("assets/day8/input_test2");
+        let (directions, network) = Network::parse(input).unwrap();
+
+        let path = network.walk(&directions, network.id("AAA").unwrap(), 6);
+        let names = path
+            .iter()
+            .map(|node| network.names[*node].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["AAA", "BBB", "AAA", "BBB", "AAA", "BBB", "ZZZ"]);
+
+        let (start, end) = (network.id("AAA"), network.id("ZZZ"));
+        let dot = network.to_dot(start, end, Some(&path));
+        let expected = [
+            "digraph network {",
+            "    \"AAA\" [color=darkgreen, style=filled, fillcolor=palegreen, shape=doublecircle];",
+            "    \"ZZZ\" [color=firebrick, style=filled, fillcolor=salmon, shape=doublecircle];",
+            "    \"AAA\" -> \"BBB\" [label=\"LR\", color=blue, penwidth=2];",
+            "    \"BBB\" -> \"AAA\" [label=\"L\", color=blue, penwidth=2];",
+            "    \"BBB\" -> \"ZZZ\" [label=\"R\", color=blue, penwidth=2];",
+            "    \"ZZZ\" -> \"ZZZ\" [label=\"LR\"];",
+            "}",
+            "",
+        ];
+        assert_eq!(dot, expected.join("\n"));
+
+        let dot = network.to_dot(start, end, None);
+        assert!(!dot.contains("color=blue"));
+
+        // a walk from BBB to AAA, the ghost nodes keep their outlines
+        let dot = network_dot(input, "BBB", "AAA").unwrap();
+        let expected = [
+            "digraph network {",
+            "    \"AAA\" [color=darkgreen, style=filled, fillcolor=salmon, shape=doublecircle];",
+            "    \"BBB\" [style=filled, fillcolor=palegreen, shape=doublecircle];",
+            "    \"ZZZ\" [color=firebrick];",
+            "    \"AAA\" -> \"BBB\" [label=\"LR\"];",
+            "    \"BBB\" -> \"AAA\" [label=\"L\", color=blue, penwidth=2];",
+            "    \"BBB\" -> \"ZZZ\" [label=\"R\"];",
+            "    \"ZZZ\" -> \"ZZZ\" [label=\"LR\"];",
+            "}",
+            "",
+        ];
+        assert_eq!(dot, expected.join("\n"));
+    }
+
+    #[test]
+    fn jump_table_matches_single_steps() {
+        let input = include_str!("assets/day8/input_test3");
+        let (directions, network) = Network::parse(input).unwrap();
+        let jump_table = JumpTable::new(&network, &directions);
+
+        for start in 0..network.names.len() {
+            let path = network.walk(&directions, start, 20);
+
+            for (steps, node) in path.iter().enumerate() {
+                assert_eq!(jump_table.after_steps(start, steps as u64), *node);
+            }
+        }
+    }
+
+    #[test]
+    fn jump_table_records_z_hits_per_pass() {
+        let input = include_str!("assets/day8/input_test3");
+        let (directions, network) = Network::parse(input).unwrap();
+        let jump_table = JumpTable::new(&network, &directions);
+
+        let node = |name| network.id(name).unwrap();
+
+        assert_eq!(jump_table.z_hits[node("11A")], Vec::<usize>::new());
+        assert_eq!(jump_table.z_hits[node("11Z")], vec![0]);
+        assert_eq!(jump_table.z_hits[node("22C")], vec![1]);
+        assert_eq!(jump_table.jumps[0][node("22A")], node("22C"));
+
+        assert_eq!(jump_table.first_z(node("11A")), Some(2));
+        assert_eq!(jump_table.first_z(node("22A")), Some(3));
+        assert_eq!(jump_table.first_z(node("XXX")), None);
+    }
+
+    #[test]
+    fn jump_table_handles_long_walks() {
+        let input = include_str!("assets/day8/input_test3");
+        let (directions, network) = Network::parse(input).unwrap();
+        let jump_table = JumpTable::new(&network, &directions);
+
+        // 22A enters a loop of length 3 (22B, 22C, 22Z) after one step
+        let start = network.id("22A").unwrap();
+        let loop_nodes = ["22B", "22C", "22Z"];
+
+        for steps in [1_000_000_000_000, u64::MAX - 1, u64::MAX] {
+            let node = jump_table.after_steps(start, steps);
+            assert_eq!(network.names[node], loop_nodes[((steps - 1) % 3) as usize]);
+        }
+    }
+
+    #[test]
+    fn parses_network_with_typed_errors() {
+        let parse = |input: &str| Network::parse(input).err();
+
+        assert_eq!(
+            parse("LR\nAAA = (AAA, AAA)"),
+            Some(ParseError::MissingNodes)
+        );
+        assert_eq!(
+            parse("\n\nAAA = (AAA, AAA)"),
+            Some(ParseError::EmptyDirections)
+        );
+        assert_eq!(
+            parse("LRX\n\nAAA = (AAA, AAA)"),
+            Some(ParseError::InvalidDirection {
+                position: 3,
+                value: 'X'
+            })
+        );
+        assert_eq!(
+            parse("L\n\nAAA = (AAA, AAA)\nBBB = AAA, AAA"),
+            Some(ParseError::InvalidNode { line: 4 })
+        );
+        assert_eq!(
+            parse("L\n\nAAA = (AAA, AAA)\nAAA = (AAA, AAA)"),
+            Some(ParseError::DuplicateNode {
+                line: 4,
+                name: "AAA".to_string()
+            })
+        );
+        assert_eq!(
+            parse("L\n\nAAA = (AAA, AAA)\nBBB = (AAA, CCC)\n"),
+            Some(ParseError::UnknownNode {
+                line: 4,
+                name: "CCC".to_string()
+            })
+        );
+
+        let missing = |name: &str| {
+            Err(ParseError::MissingNode {
+                name: name.to_string(),
+            })
+        };
+        assert_eq!(
+            solve_part1("L\n\nAAA = (AAA, AAA)\n", "AAA", "ZZZ"),
+            missing("ZZZ")
+        );
+        assert_eq!(
+            solve_part1("L\n\nZZZ = (ZZZ, ZZZ)\n", "AAA", "ZZZ"),
+            missing("AAA")
+        );
+
+        let (directions, network) = Network::parse("LR\n\nAAA = (AAA, AAA)\n\n").unwrap();
+        assert_eq!(directions.len(), 2);
+        assert_eq!(network.names, vec!["AAA"]);
+    }
+
+    #[test]
+    fn renders_network_without_path_to_end() {
+        let input = "L\n\nAAA = (AAA, AAA)\nBBZ = (BBZ, BBZ)\n";
+        let (_, network) = Network::parse(input).unwrap();
+        assert_eq!(
+            network_dot(input, "AAA", "ZZZ"),
+            Ok(network.to_dot(network.id("AAA"), network.id("ZZZ"), None))
+        );
+
+        // ZZZ exists but can't be reached from AAA
+        let input = "L\n\nAAA = (AAA, AAA)\nZZZ = (ZZZ, ZZZ)\n";
+        let (_, network) = Network::parse(input).unwrap();
+        assert_eq!(
+            network_dot(input, "AAA", "ZZZ"),
+            Ok(network.to_dot(network.id("AAA"), network.id("ZZZ"), None))
+        );
+
+        let input = include_str!("assets/day8/input_test2");
+        let dot = network_dot(input, "AAA", "ZZZ").unwrap();
+        assert!(dot.contains("color=blue"));
+    }
+
+    #[test]
+    fn node_after_reports_missing_start() {
+        let input = include_str!("assets/day8/input_test2");
+        assert_eq!(
+            node_after(input, "AAA", 6),
+            Ok(("ZZZ".to_string(), Some(6)))
+        );
+        assert_eq!(
+            node_after(input, "CCC", 6),
+            Err(ParseError::MissingNode {
+                name: "CCC".to_string()
+            })
+        );
+    }
+
+    // one ghost per length, each alone on a loop with a single Z node right
+    // before its start, so all of them meet after the product of lengths - 1 steps
+    fn prime_loops(lengths: &[usize]) -> String {
+        let mut input = String::from("L\n\n");
+
+        for (ghost, length) in lengths.iter().enumerate() {
+            let name = |node: usize| match node {
+                0 => format!("G{}A", ghost),
+                node if node == length - 1 => format!("G{}Z", ghost),
+                node => format!("G{}N{}", ghost, node),
+            };
+
+            for node in 0..*length {
+                let next = name((node + 1) % length);
+                input.push_str(&format!("{} = ({}, {})\n", name(node), next, next));
+            }
+        }
+
+        input
+    }
+
+    #[test]
+    fn part2_reports_step_overflow() {
+        const PRIMES: [usize; 13] = [
+            997, 991, 983, 977, 971, 967, 953, 947, 941, 937, 929, 919, 911,
+        ];
+
+        let input = prime_loops(&PRIMES[..3]);
+        assert_eq!(solve_part2(&input), Ok(Some(997 * 991 * 983 - 1)));
+
+        // above u64::MAX but still within i128
+        let input = prime_loops(&PRIMES[..7]);
+        assert_eq!(solve_part2(&input), Err(SolveError::StepOverflow));
+
+        // the combined modulus no longer fits into i128
+        let input = prime_loops(&PRIMES);
+        assert_eq!(solve_part2(&input), Err(SolveError::StepOverflow));
     }
 }